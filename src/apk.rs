@@ -0,0 +1,92 @@
+use anyhow::Result;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// Checks whether the given byte array is a ZIP archive, such as an Android package.
+pub fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+}
+
+/// Reads the native libraries from the Android package in the given byte array. These are the
+/// shared objects stored as `lib/<abi>/<name>.so`.
+///
+/// Yields the ABI, the file name and the contents of every native library.
+pub fn native_libraries(bytes: &[u8]) -> Result<Vec<(String, String, Vec<u8>)>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let mut libraries = vec![];
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+
+        if !file.is_file() {
+            continue;
+        }
+
+        // Check if the path is of the form `lib/<abi>/<name>.so`. The ABI and the name end up in
+        // the output path, so they must not be able to escape the output directory.
+        let parts: Vec<&str> = file.name().split('/').collect();
+        let is_component = |part: &str| !matches!(part, "" | "." | "..") && !part.contains('\\');
+
+        let (abi, name) = match parts[..] {
+            ["lib", abi, name] if is_component(abi) && is_component(name) && name.ends_with(".so") => {
+                (abi.to_string(), name.to_string())
+            }
+            _ => continue,
+        };
+
+        // Decompress the library.
+        let mut library = vec![];
+        file.read_to_end(&mut library)?;
+
+        libraries.push((abi, name, library));
+    }
+
+    Ok(libraries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    /// Builds a ZIP archive holding the given files.
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+
+        for (name, contents) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn reads_native_libraries() {
+        let bytes = archive(&[
+            ("lib/arm64-v8a/libapp.so", b"\x7fELF"),
+            ("lib/arm64-v8a/libapp.txt", b"text"),
+            ("assets/lib/x86/libapp.so", b"\x7fELF"),
+        ]);
+
+        assert!(is_zip(&bytes));
+        assert_eq!(
+            native_libraries(&bytes).unwrap(),
+            vec![("arm64-v8a".to_string(), "libapp.so".to_string(), b"\x7fELF".to_vec())],
+        );
+    }
+
+    #[test]
+    fn rejects_escaping_paths() {
+        let bytes = archive(&[
+            ("lib/../app.so", b"a"),
+            ("lib/./app.so", b"b"),
+            ("lib//app.so", b"c"),
+            ("lib/..\\..\\x86/app.so", b"d"),
+            ("lib/x86/..\\app.so", b"e"),
+        ]);
+
+        assert!(native_libraries(&bytes).unwrap().is_empty());
+    }
+}
@@ -1,6 +1,258 @@
+use iced_x86::{Decoder, DecoderOptions, Instruction, Mnemonic, OpKind, Register};
 use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
 
-use crate::executable::ExecutableMapping;
+use crate::executable::{Architecture, ExecutableMapping};
+use crate::wasm;
+
+/// Checks whether the code in the file range `range` references the file offset `target`, i.e.
+/// whether any of its instructions forms the address of the target, e.g. `lea reg, [rip + disp32]`,
+/// `push imm32`, or `adrp` followed by `add`. The instructions are decoded with every instruction
+/// set the mapping may hold.
+pub fn references(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    range: Range<usize>,
+    target: usize,
+) -> bool {
+    let matches = |value: u64| resolve_address(mapping, value) == Some(target);
+    let mut found = false;
+
+    // We can only trust the architecture and the byte order if we know the architecture, so raw
+    // images are decoded with every instruction set in both byte orders.
+    let (architectures, byte_orders) = match mapping.architecture() {
+        Architecture::Unknown => (ARCHITECTURES.to_vec(), vec![true, false]),
+        architecture => (vec![architecture], vec![mapping.is_little_endian()]),
+    };
+
+    for architecture in architectures {
+        match architecture {
+            Architecture::X86 | Architecture::X86_64 => {
+                let bitness = if architecture == Architecture::X86 { 32 } else { 64 };
+
+                decode_x86_range(bytes, mapping, range.clone(), bitness, |_, instruction| {
+                    for operand in 0..instruction.op_count() {
+                        let value = match instruction.op_kind(operand) {
+                            OpKind::Memory => memory_address(instruction),
+                            _ => immediate(instruction, operand),
+                        };
+
+                        found |= value.is_some_and(matches);
+                    }
+                });
+            }
+            Architecture::Arm => {
+                // Thumb functions have the lowest bit of their address set.
+                let range = range.start & !1..range.end;
+
+                for thumb in [false, true] {
+                    let decode = |offset, pc, registers: &Registers| decode_arm(bytes, mapping, thumb, offset, pc, registers);
+
+                    sweep_registers(mapping, [None; 32], range.clone(), decode, |_, _, value, _| found |= matches(value));
+                }
+            }
+            Architecture::AArch64 => {
+                let decode = |offset, pc, registers: &Registers| Some((4, decode_a64(bytes, mapping, offset, pc, registers)?));
+
+                sweep_registers(mapping, [None; 32], range.clone(), decode, |_, _, value, _| found |= matches(value));
+            }
+            Architecture::RiscV => {
+                let decode = |offset, pc, registers: &Registers| decode_riscv(bytes, offset, pc, registers);
+
+                sweep_registers(mapping, riscv_registers(mapping), range.clone(), decode, |_, _, value, _| found |= matches(value));
+            }
+            Architecture::Mips => {
+                for &little_endian in &byte_orders {
+                    let decode = |offset, _, registers: &Registers| {
+                        Some((4, decode_mips(bytes, mapping, offset, little_endian, registers)?))
+                    };
+
+                    sweep_registers(mapping, mips_registers(mapping), range.clone(), decode, |_, _, value, _| found |= matches(value));
+                }
+            }
+            Architecture::PowerPC => {
+                for &little_endian in &byte_orders {
+                    let decode = |offset, _, registers: &Registers| {
+                        Some((4, decode_ppc(bytes, offset, little_endian, registers)?))
+                    };
+
+                    sweep_registers(mapping, ppc_registers(mapping), range.clone(), decode, |_, _, value, _| found |= matches(value));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    found
+}
+
+/// The architectures whose code `references` can decode.
+const ARCHITECTURES: [Architecture; 7] = [
+    Architecture::X86,
+    Architecture::X86_64,
+    Architecture::Arm,
+    Architecture::AArch64,
+    Architecture::RiscV,
+    Architecture::Mips,
+    Architecture::PowerPC,
+];
+
+/// Yields the address of the memory operand of the given instruction, if it is either RIP-relative
+/// or absolute.
+pub fn memory_address(instruction: &Instruction) -> Option<u64> {
+    if instruction.is_ip_rel_memory_operand() {
+        return Some(instruction.ip_rel_memory_address());
+    }
+
+    if instruction.memory_base() == Register::None && instruction.memory_index() == Register::None {
+        return Some(instruction.memory_displacement64());
+    }
+
+    None
+}
+
+/// Yields the immediate operand `operand` of the given instruction, if any.
+pub fn immediate(instruction: &Instruction, operand: u32) -> Option<u64> {
+    match instruction.op_kind(operand) {
+        OpKind::Immediate8to32 | OpKind::Immediate32 | OpKind::Immediate32to64 | OpKind::Immediate64 => {
+            Some(instruction.immediate(operand))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes the x86 instructions in the code ranges of the mapping `mapping` using the given
+/// bitness, and calls `visit` with the file offset of every instruction and the instruction
+/// itself. The instruction pointer of every instruction is its virtual address.
+pub fn decode_x86<F: FnMut(usize, &Instruction)>(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    bitness: u32,
+    mut visit: F,
+) {
+    for code_range in mapping.code_ranges() {
+        decode_x86_range(bytes, mapping, code_range, bitness, &mut visit);
+    }
+}
+
+/// Decodes the x86 instructions in the file range `range` like `decode_x86`.
+fn decode_x86_range<F: FnMut(usize, &Instruction)>(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    range: Range<usize>,
+    bitness: u32,
+    mut visit: F,
+) {
+    let code = match bytes.get(range.clone()) {
+        Some(code) => code,
+        _ => return,
+    };
+
+    let ip = match mapping.file_offset_to_rva(range.start) {
+        Some(ip) => ip as u64,
+        _ => return,
+    };
+
+    let mut decoder = Decoder::with_ip(bitness, code, ip, DecoderOptions::NONE);
+    let mut instruction = Instruction::default();
+
+    while decoder.can_decode() {
+        decoder.decode_out(&mut instruction);
+
+        if instruction.is_invalid() {
+            continue;
+        }
+
+        visit(range.start + (instruction.ip() - ip) as usize, &instruction);
+    }
+}
+
+/// Scores the constants `constants`, which consist of the file offset of the instruction and the
+/// file offset it references, by the distance of the instruction to the closest of the instructions
+/// at the known offsets `known_offsets`.
+///
+/// Yields a map of the distance scores to the referenced file offsets.
+fn score_constants(known_offsets: BTreeSet<usize>, constants: Vec<(usize, usize)>) -> BTreeMap<usize, usize> {
+    let known_offsets: Vec<usize> = known_offsets.into_iter().collect();
+    let mut scores: BTreeMap<usize, usize> = BTreeMap::new();
+
+    if known_offsets.is_empty() {
+        return scores;
+    }
+
+    for (offset, value) in constants {
+        // Find the closest known offset to this instruction.
+        let closest = closest_offset(&known_offsets, offset);
+
+        // Track the value and distance score.
+        scores.insert(offset.abs_diff(closest), value);
+    }
+
+    scores
+}
+
+/// Finds the offset in the ordered list of known offsets `known_offsets` that is closest to the
+/// given offset.
+fn closest_offset(known_offsets: &[usize], offset: usize) -> usize {
+    match known_offsets.binary_search(&offset) {
+        Ok(index) => known_offsets[index],
+        Err(index) => if index >= known_offsets.len() {
+            known_offsets[index - 1]
+        } else if index == 0 {
+            known_offsets[0]
+        } else {
+            let lhs = known_offsets[index - 1];
+            let rhs = known_offsets[index];
+
+            if lhs.abs_diff(offset) < rhs.abs_diff(offset) {
+                lhs
+            } else {
+                rhs
+            }
+        }
+    }
+}
+
+/// Scans the byte array for code that passes the tree at the file offset `tree_offset` and the
+/// names at the file offset `name_offset` to `qRegisterResourceData`, and collects the candidates
+/// for the data argument.
+///
+/// Yields a map of the distance scores to the file offsets of the candidates.
+pub type Locator = fn(&[u8], &ExecutableMapping, usize, usize) -> BTreeMap<usize, usize>;
+
+/// Selects the locators that apply to the given architecture, in the order they should be tried,
+/// along with a description of the references they look for. Raw images and process memory are of
+/// unknown architecture, so they are tried with every locator. The relocated pointers are not tied
+/// to any instruction set and are tried last.
+pub fn locators(architecture: Architecture) -> Vec<(&'static str, Locator)> {
+    let mut locators: Vec<(&'static str, Locator)> = match architecture {
+        Architecture::X86 => vec![
+            ("PUSH instruction", find_blobs_push),
+            ("MOV instruction", find_blobs_mov_stack),
+        ],
+        Architecture::X86_64 => vec![("LEA or MOV instruction", find_blobs_lea)],
+        Architecture::AArch64 => vec![("ADRP instruction", find_blobs_adrp)],
+        Architecture::Arm => vec![("ARM instruction", find_blobs_arm)],
+        Architecture::RiscV => vec![("RISC-V instruction", find_blobs_riscv)],
+        Architecture::Mips => vec![("MIPS instruction", find_blobs_mips)],
+        Architecture::PowerPC => vec![("PowerPC instruction", find_blobs_ppc)],
+        Architecture::Wasm => vec![("I32.CONST instruction", find_blobs_i32_const)],
+        Architecture::Unknown => vec![
+            ("PUSH instruction", find_blobs_push),
+            ("LEA or MOV instruction", find_blobs_lea),
+            ("MOV instruction", find_blobs_mov_stack),
+            ("ADRP instruction", find_blobs_adrp),
+            ("ARM instruction", find_blobs_arm),
+            ("RISC-V instruction", find_blobs_riscv),
+            ("MIPS instruction", find_blobs_mips),
+            ("PowerPC instruction", find_blobs_ppc),
+            ("I32.CONST instruction", find_blobs_i32_const),
+        ],
+    };
+
+    locators.push(("relocated pointer", find_blobs_relocations));
+    locators
+}
 
 pub fn find_blobs_push(
     bytes: &[u8],
@@ -10,180 +262,1726 @@ pub fn find_blobs_push(
 ) -> BTreeMap<usize, usize> {
     let mut known_offsets = BTreeSet::new();
 
-    for (offset, window) in bytes.windows(5).enumerate() {
+    // Collect the push instructions and their operands.
+    let mut constants = vec![];
+
+    decode_x86(bytes, mapping, 32, |offset, instruction| {
         // Look for the push instruction.
-        if window[0] != 0x68 {
-            continue;
+        if instruction.mnemonic() != Mnemonic::Push || instruction.op0_kind() != OpKind::Immediate32 {
+            return;
         }
 
-        // Decode the relative offset.
-        let mut slice = [0u8; 4];
-        slice.copy_from_slice(&window[1..]);
-        let value = u32::from_ne_bytes(slice) as usize;
+        // If the image has base relocations, an address is only pushed if the immediate, which is
+        // encoded in the last four bytes of the instruction, is relocated.
+        if mapping.has_relocations() && !mapping.is_relocated(instruction.next_ip() as usize - 4) {
+            return;
+        }
 
         // Look up the file offset.
-        let value = match mapping.rva_to_file_offset(value) {
+        let value = match mapping.rva_to_file_offset(instruction.immediate32() as usize) {
             Some(value) => value,
-            _ => continue,
+            _ => return,
         };
 
         // Check if we found a push with the right offset.
         if value == tree_offset || value == name_offset {
             known_offsets.insert(offset);
+        } else {
+            constants.push((offset, value));
         }
-    }
+    });
 
     // Now that we have a set of known offsets, we can try and find the push instruction referencing
     // the blob offset.
-    let known_offsets: Vec<usize> = known_offsets.into_iter().collect();
-    let mut scores: BTreeMap<usize, usize> = BTreeMap::new();
+    score_constants(known_offsets, constants)
+}
 
-    if known_offsets.is_empty() {
-        return scores;
-    }
+/// The registers that hold the tree, the names and the data arguments in the Windows x64 calling
+/// convention.
+const WIN64_REGISTERS: [Register; 3] = [Register::RDX, Register::R8, Register::R9];
 
-    for (offset, window) in bytes.windows(5).enumerate() {
-        // Look for the push instruction.
-        if window[0] != 0x68 {
-            continue;
-        }
+/// The registers that hold the tree, the names and the data arguments in the System V AMD64
+/// calling convention.
+const SYSV_REGISTERS: [Register; 3] = [Register::RSI, Register::RDX, Register::RCX];
+
+pub fn find_blobs_lea(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    // Try both calling conventions if we don't know what kind of image this is.
+    let conventions = match mapping.architecture() {
+        Architecture::Unknown => vec![SYSV_REGISTERS, WIN64_REGISTERS],
+        _ if mapping.is_pe() => vec![WIN64_REGISTERS],
+        _ => vec![SYSV_REGISTERS],
+    };
 
-        // Decode the relative offset.
-        let mut slice = [0u8; 4];
-        slice.copy_from_slice(&window[1..]);
-        let value = u32::from_ne_bytes(slice) as usize;
+    // Collect the addresses that are loaded into registers, either RIP-relative through lea, or as
+    // immediates through mov in non-PIE code, e.g. `mov esi, imm32`.
+    let mut loads = vec![];
+
+    decode_x86(bytes, mapping, 64, |offset, instruction| {
+        let address = match instruction.mnemonic() {
+            Mnemonic::Lea if instruction.is_ip_rel_memory_operand() => instruction.ip_rel_memory_address(),
+            Mnemonic::Mov if instruction.op0_kind() == OpKind::Register => match instruction.op1_kind() {
+                OpKind::Immediate32 | OpKind::Immediate32to64 | OpKind::Immediate64 => instruction.immediate(1),
+                _ => return,
+            },
+            _ => return,
+        };
 
         // Look up the file offset.
-        let value = match mapping.rva_to_file_offset(value) {
+        let value = match mapping.rva_to_file_offset(address as usize) {
             Some(value) => value,
-            _ => continue,
+            _ => return,
         };
 
-        // Find the closest known offset to this instruction.
-        let closest = match known_offsets.binary_search(&offset) {
-            Ok(index) => continue,
-            Err(index) => if index >= known_offsets.len() {
-                known_offsets[index - 1]
-            } else if index == 0 {
-                known_offsets[0]
-            } else {
-                let lhs = known_offsets[index - 1];
-                let rhs = known_offsets[index];
+        loads.push((offset, instruction.op0_register().full_register(), value));
+    });
 
-                if lhs.abs_diff(offset) < rhs.abs_diff(offset) {
-                    lhs
-                } else {
-                    rhs
-                }
+    for [tree_reg, name_reg, blob_reg] in conventions {
+        let mut known_offsets = BTreeSet::new();
+        let mut constants = vec![];
+
+        for &(offset, register, value) in &loads {
+            // Check if we found a load with the right tree offset or name offset.
+            if (register == tree_reg && value == tree_offset) || (register == name_reg && value == name_offset) {
+                known_offsets.insert(offset);
+            } else if register == blob_reg {
+                constants.push((offset, value));
             }
-        };
+        }
 
-        // Track the value and distance score.
-        scores.insert(offset.abs_diff(closest), value);
+        // Now that we have a set of known offsets, we can try and find the load referencing the
+        // blob offset.
+        let scores = score_constants(known_offsets, constants);
+
+        if !scores.is_empty() {
+            return scores;
+        }
     }
 
-    scores
+    BTreeMap::new()
 }
 
-pub fn find_blobs_lea(
+pub fn find_blobs_mov_stack(
     bytes: &[u8],
     mapping: &ExecutableMapping,
     tree_offset: usize,
     name_offset: usize,
-    is_win: bool,
 ) -> BTreeMap<usize, usize> {
     let mut known_offsets = BTreeSet::new();
 
-    let (tree_reg, name_reg, blob_reg) = if is_win {
-        // RDX, R8, R9
-        (0x15, 0x05, 0x0d)
-    } else {
-        // RSI, RDX, RCX
-        (0x35, 0x15, 0x0d)
-    };
+    // Instead of pushing the arguments, GCC stores them relative to the stack pointer, e.g.
+    // `mov dword ptr [esp+4], imm32`.
+    // The stack slots of the tree, names and data arguments.
+    let (tree_slot, name_slot, blob_slot) = (4, 8, 12);
 
-    for (offset, window) in bytes.windows(6).enumerate() {
-        // Look for the lea instruction.
-        if window[0] != 0x8d {
-            continue;
-        }
+    // Collect the mov instructions with the right stack slot and their operands.
+    let mut constants = vec![];
 
-        // Decode the relative offset.
-        let mut slice = [0u8; 4];
-        slice.copy_from_slice(&window[2..]);
-        let value = u32::from_ne_bytes(slice) as usize;
+    decode_x86(bytes, mapping, 32, |offset, instruction| {
+        // Look for the mov instruction storing an immediate relative to the stack pointer.
+        if instruction.mnemonic() != Mnemonic::Mov || instruction.op0_kind() != OpKind::Memory || instruction.op1_kind() != OpKind::Immediate32 {
+            return;
+        }
 
-        // Calculate the absolute address.
-        let value = offset + value + 6;
+        if instruction.memory_base() != Register::ESP || instruction.memory_index() != Register::None {
+            return;
+        }
 
         // Look up the file offset.
-        let value = match mapping.rva_to_file_offset(value) {
+        let value = match mapping.rva_to_file_offset(instruction.immediate32() as usize) {
             Some(value) => value,
-            _ => continue,
+            _ => return,
         };
 
-        // Check if we found a lea with the right tree offset.
-        if window[1] == tree_reg && value == tree_offset {
-            known_offsets.insert(offset);
-        }
+        let slot = instruction.memory_displacement32();
 
-        // Check if we found a lea with the right name offset.
-        if window[1] == name_reg && value == name_offset {
+        // Check if we found a mov with the right tree offset or name offset.
+        if (slot == tree_slot && value == tree_offset) || (slot == name_slot && value == name_offset) {
             known_offsets.insert(offset);
+        } else if slot == blob_slot {
+            constants.push((offset, value));
         }
-    }
+    });
 
-    // Now that we have a set of known offsets, we can try and find the lea instruction referencing
+    // Now that we have a set of known offsets, we can try and find the mov instruction referencing
     // the blob offset.
-    let known_offsets: Vec<usize> = known_offsets.into_iter().collect();
-    let mut scores: BTreeMap<usize, usize> = BTreeMap::new();
+    score_constants(known_offsets, constants)
+}
 
-    if known_offsets.is_empty() {
-        return scores;
+/// Decodes the 32-bit little-endian word at the given offset.
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let mut slice = [0u8; 4];
+    slice.copy_from_slice(bytes.get(offset..)?.get(..4)?);
+
+    Some(u32::from_le_bytes(slice))
+}
+
+/// Sign-extends the lower `bits` bits of the given value.
+fn sign_extend(value: u64, bits: u32) -> u64 {
+    let shift = 64 - bits;
+
+    (((value << shift) as i64) >> shift) as u64
+}
+
+/// Decodes the AArch64 instruction at the given offset with the program counter `pc`, given the
+/// current values of the registers `registers`. The instructions are always little-endian, even
+/// if the data is not.
+///
+/// Yields the effect of the instruction, or `None` if it lies outside of the byte array.
+fn decode_a64(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    offset: usize,
+    pc: u64,
+    registers: &Registers,
+) -> Option<Effect> {
+    let instruction = read_u32_le(bytes, offset)? as u64;
+    let rd = (instruction & 0x1f) as usize;
+    let rn = ((instruction >> 5) & 0x1f) as usize;
+    let rt2 = ((instruction >> 10) & 0x1f) as usize;
+    let rm = ((instruction >> 16) & 0x1f) as usize;
+
+    // Loads the 64-bit word at the given virtual address, e.g. from the GOT.
+    let load = |address: u64| read_u64(bytes, resolve_address(mapping, address)?, mapping.is_little_endian());
+
+    let effect = if instruction & 0x9f00_0000 == 0x9000_0000 {
+        // Decode adrp xd, label.
+        let immediate = (instruction >> 5) & 0x7ffff;
+        let page = sign_extend((immediate << 2 | (instruction >> 29) & 0x3) << 12, 33);
+
+        Effect::Load { register: rd, value: (pc & !0xfff).wrapping_add(page), derived: false }
+    } else if instruction & 0x9f00_0000 == 0x1000_0000 {
+        // Decode adr xd, label.
+        let immediate = (instruction >> 5) & 0x7ffff;
+
+        Effect::Load { register: rd, value: pc.wrapping_add(sign_extend(immediate << 2 | (instruction >> 29) & 0x3, 21)), derived: false }
+    } else if instruction & 0xff80_0000 == 0x9100_0000 {
+        // Decode add xd, xn, #imm12, which may shift the immediate by 12 bits.
+        let immediate = ((instruction >> 10) & 0xfff) << (12 * ((instruction >> 22) & 0x1));
+
+        Effect::load(rd, registers[rn].map(|value| value.wrapping_add(immediate)), rn == rd)
+    } else if instruction & 0xffc0_0000 == 0xf940_0000 {
+        // Decode ldr xt, [xn, #imm12 * 8], which loads the address from the GOT.
+        let address = registers[rn].map(|value| value.wrapping_add(((instruction >> 10) & 0xfff) << 3));
+
+        Effect::load(rd, address.and_then(load), rn == rd)
+    } else if instruction & 0xff00_0000 == 0x5800_0000 {
+        // Decode ldr xt, label, which loads the address from the literal pool.
+        Effect::load(rd, load(pc.wrapping_add(sign_extend(((instruction >> 5) & 0x7ffff) << 2, 21))), false)
+    } else if instruction & 0xffe0_ffe0 == 0xaa00_03e0 {
+        // Decode mov xd, xm, which copies the value.
+        Effect::load(rd, registers[rm], false)
+    } else if instruction & 0x1c00_0000 == 0x1000_0000 {
+        // Decode the other data processing instructions with an immediate.
+        Effect::Clobber(1 << rd)
+    } else if instruction & 0xffe0_0000 == 0xd520_0000 {
+        // Decode mrs xt, sysreg and sysl xt, ..., which read a system register into xt.
+        Effect::Clobber(1 << rd)
+    } else if instruction & 0xff00_0000 == 0xd500_0000 {
+        // Decode the other system instructions, such as the hints and the barriers.
+        Effect::None
+    } else if instruction & 0x1c00_0000 == 0x1400_0000 {
+        // Decode the branches, e.g. b, bl, b.cond, cbz, tbz, br, blr and ret, as well as the
+        // exception generating instructions.
+        Effect::Branch
+    } else if instruction & 0x0a00_0000 == 0x0800_0000 {
+        // Decode the loads and stores, which write the registers they load, as well as the base
+        // register if they write back the address. The loads into the SIMD and floating-point
+        // registers don't write any general-purpose register but the base register.
+        let vector = instruction & (1 << 26) != 0;
+        let is_load = instruction & (1 << 22) != 0;
+
+        let (loaded, writeback) = match (instruction >> 28) & 0x3 {
+            // Decode the exclusives, the ordered loads and stores, and the structure loads and
+            // stores, where the exclusive stores write their status to the register in place of xm.
+            0 => (1 << rd | 1 << rt2 | 1 << rm, vector && instruction & (1 << 23) != 0),
+            // Decode the other loads from the literal pool.
+            1 => (1 << rd, false),
+            // Decode the pairs, which write back the address if they are pre- or post-indexed.
+            2 => (if is_load { 1 << rd | 1 << rt2 } else { 0 }, instruction & (1 << 23) != 0),
+            // Decode the single registers, where the atomics always write xt, and the others load
+            // unless their opc is zero.
+            _ => {
+                let unscaled = instruction & (1 << 24) == 0;
+                let writeback = unscaled && instruction & (1 << 21) == 0 && instruction & (1 << 10) != 0;
+                let atomic = unscaled && instruction & (1 << 21) != 0 && (instruction >> 10) & 0x3 == 0;
+
+                (if atomic || (instruction >> 22) & 0x3 != 0 { 1 << rd } else { 0 }, writeback)
+            }
+        };
+
+        match if vector { 0 } else { loaded } | if writeback { 1 << rn } else { 0 } {
+            0 => Effect::None,
+            mask => Effect::Clobber(mask),
+        }
+    } else if instruction & 0x0e00_0000 == 0x0a00_0000 {
+        // Decode the data processing instructions with registers, which all write xd.
+        Effect::Clobber(1 << rd)
+    } else if instruction & 0x5f20_fc00 == 0x1e20_0000 || instruction & 0xbfe0_ec00 == 0x0e00_2c00 {
+        // Decode the conversions between floating-point and integer registers, as well as
+        // umov wd, vn.s[index] and smov wd, vn.h[index], which may write xd.
+        Effect::Clobber(1 << rd)
+    } else {
+        Effect::None
+    };
+
+    Some(effect)
+}
+
+pub fn find_blobs_adrp(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    // The tree, names and data are passed in x1, x2 and x3.
+    let decode = |offset, pc, registers: &Registers| Some((4, decode_a64(bytes, mapping, offset, pc, registers)?));
+    let (known_offsets, constants) = track_registers(mapping, [None; 32], (1, 2, 3), tree_offset, name_offset, decode);
+
+    // Now that we have a set of known offsets, we can try and find the add or ldr instruction
+    // referencing the blob offset.
+    score_constants(known_offsets, constants)
+}
+
+/// Loads the 32-bit little-endian word at the given virtual address.
+fn load_u32_le(bytes: &[u8], mapping: &ExecutableMapping, address: u32) -> Option<u32> {
+    read_u32_le(bytes, mapping.rva_to_file_offset(address as usize)?)
+}
+
+/// The values of the general-purpose registers, where `None` means that the value is not a known
+/// constant.
+type Registers = [Option<u64>; 32];
+
+/// The effect of an instruction on the general-purpose registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Effect {
+    /// The instruction doesn't write any general-purpose register.
+    None,
+    /// The instruction loads the constant `value` into the register `register`, where `derived`
+    /// tells whether the constant was derived from the previous value of that register, e.g. by
+    /// adding the lower half of an address to the upper half.
+    Load { register: usize, value: u64, derived: bool },
+    /// The instruction writes values that are not known constants to the registers in the mask.
+    Clobber(u32),
+    /// The instruction transfers control, e.g. a branch, a call or a return, after which the
+    /// values of the registers can no longer be relied upon.
+    Branch,
+    /// Like `Branch`, except that the instruction in the delay slot is executed first.
+    DelayedBranch,
+}
+
+impl Effect {
+    /// Loads the value into the register if it is a known constant, or clobbers the register
+    /// otherwise.
+    fn load(register: usize, value: Option<u64>, derived: bool) -> Self {
+        match value {
+            Some(value) => Effect::Load { register, value, derived },
+            _ => Effect::Clobber(1 << register),
+        }
     }
+}
 
-    for (offset, window) in bytes.windows(6).enumerate() {
-        // Look for lea with the right destination register.
-        if window[0] != 0x8d || window[1] != blob_reg {
-            continue;
+/// Decodes the A32 instruction at the given offset with the program counter `pc`, given the
+/// current values of the registers `registers`.
+///
+/// Yields the effect of the instruction, or `None` if it lies outside of the byte array.
+fn decode_a32(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    offset: usize,
+    pc: u64,
+    registers: &Registers,
+) -> Option<Effect> {
+    let instruction = read_u32_le(bytes, offset)?;
+    let rd = ((instruction >> 12) & 0xf) as usize;
+    let rn = ((instruction >> 16) & 0xf) as usize;
+    let register = |index: usize| registers[index].map(|value| value as u32);
+
+    // Reading the program counter yields the address of the instruction plus 8.
+    let pc = (pc as u32).wrapping_add(8);
+
+    // The unconditional instructions include blx label, but none that write a register.
+    if instruction >> 28 == 0xf {
+        if instruction & 0x0e00_0000 == 0x0a00_0000 {
+            return Some(Effect::Branch);
         }
 
-        // Decode the relative offset.
-        let mut slice = [0u8; 4];
-        slice.copy_from_slice(&window[2..]);
-        let value = u32::from_ne_bytes(slice) as usize;
+        return Some(Effect::None);
+    }
 
-        // Calculate the absolute address.
-        let value = offset + value + 6;
+    let effect = if instruction & 0x0ff0_0000 == 0x0300_0000 {
+        // Decode movw rd, #imm16.
+        let value = (instruction >> 4) & 0xf000 | instruction & 0xfff;
 
-        // Look up the file offset.
-        let value = match mapping.rva_to_file_offset(value) {
-            Some(value) => value,
-            _ => continue,
+        Effect::Load { register: rd, value: value as u64, derived: false }
+    } else if instruction & 0x0ff0_0000 == 0x0340_0000 {
+        // Decode movt rd, #imm16.
+        let value = (instruction << 12) & 0xf000_0000 | (instruction << 16) & 0x0fff_0000;
+
+        Effect::load(rd, register(rd).map(|low| (value | low & 0xffff) as u64), true)
+    } else if instruction & 0x0f7f_0000 == 0x051f_0000 {
+        // Decode ldr rd, [pc, #±imm12], which loads the constant from the literal pool.
+        let displacement = instruction & 0xfff;
+
+        let address = if instruction & (1 << 23) != 0 {
+            pc.wrapping_add(displacement)
+        } else {
+            pc.wrapping_sub(displacement)
         };
 
-        // Find the closest known offset to this instruction.
-        let closest = match known_offsets.binary_search(&offset) {
-            Ok(index) => known_offsets[index],
-            Err(index) => if index >= known_offsets.len() {
-                known_offsets[index - 1]
-            } else if index == 0 {
-                known_offsets[0]
-            } else {
-                let lhs = known_offsets[index - 1];
-                let rhs = known_offsets[index];
+        Effect::load(rd, load_u32_le(bytes, mapping, address).map(|value| value as u64), false)
+    } else if instruction & 0x0fff_0ff0 == 0x008f_0000 {
+        // Decode add rd, pc, rm, which turns a PC-relative offset into an address.
+        let rm = (instruction & 0xf) as usize;
 
-                if lhs.abs_diff(offset) < rhs.abs_diff(offset) {
-                    lhs
-                } else {
-                    rhs
-                }
-            }
+        Effect::load(rd, register(rm).map(|value| value.wrapping_add(pc) as u64), rm == rd)
+    } else if instruction & 0x0fff_0000 == 0x028f_0000 {
+        // Decode adr rd, label, i.e. add rd, pc, #imm.
+        let rotation = ((instruction >> 8) & 0xf) * 2;
+        let value = (instruction & 0xff).rotate_right(rotation);
+
+        Effect::Load { register: rd, value: pc.wrapping_add(value) as u64, derived: false }
+    } else if instruction & 0x0fff_ffd0 == 0x012f_ff10 || instruction & 0x0e00_0000 == 0x0a00_0000 {
+        // Decode bx rm, blx rm, b label and bl label.
+        Effect::Branch
+    } else if instruction & 0x0e10_0000 == 0x0810_0000 {
+        // Decode ldm rn, {registers}, which returns if it loads the program counter.
+        match instruction & 0x8000 {
+            0 => Effect::Clobber(instruction & 0xffff),
+            _ => Effect::Branch,
+        }
+    } else if instruction & 0x0f00_00f0 == 0x0000_0090 {
+        // Decode the multiplications, which write rn, and rd for the long variants.
+        Effect::Clobber(1 << rn | 1 << rd)
+    } else if instruction & 0x0e00_0090 == 0x0000_0090 {
+        // Decode the halfword and doubleword transfers, of which only the loads write rd.
+        match instruction & (1 << 20) {
+            0 => Effect::None,
+            _ => Effect::Clobber(3 << rd),
+        }
+    } else if instruction & 0x0c00_0000 == 0x0000_0000 {
+        // Decode the data-processing instructions. The comparisons only set the flags, and the
+        // same encodings without the flag set hold miscellaneous instructions such as msr.
+        let opcode = (instruction >> 21) & 0xf;
+
+        if (8..=11).contains(&opcode) {
+            Effect::None
+        } else if rd == 15 {
+            Effect::Branch
+        } else {
+            Effect::Clobber(1 << rd)
+        }
+    } else if instruction & 0x0c00_0000 == 0x0400_0000 {
+        // Decode the word and byte transfers, of which only the loads write rd, as well as the
+        // media instructions, which all write rd.
+        let is_media = instruction & 0x0200_0010 == 0x0200_0010;
+        let writeback = instruction & (1 << 24) == 0 || instruction & (1 << 21) != 0;
+
+        if !is_media && instruction & (1 << 20) == 0 {
+            Effect::None
+        } else if rd == 15 {
+            Effect::Branch
+        } else if !is_media && writeback {
+            Effect::Clobber(1 << rd | 1 << rn)
+        } else {
+            Effect::Clobber(1 << rd)
+        }
+    } else {
+        Effect::None
+    };
+
+    Some(effect)
+}
+
+/// Decodes the 32-bit Thumb instruction consisting of the halfwords `first` and `second` at the
+/// given virtual address, where `pc` and `aligned_pc` are the values of the program counter as
+/// read by the instruction.
+///
+/// Yields the effect of the instruction.
+fn decode_thumb32(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    (first, second): (u32, u32),
+    aligned_pc: u32,
+    registers: &Registers,
+) -> Effect {
+    let rd = ((second >> 8) & 0xf) as usize;
+    let rt = (second >> 12) as usize;
+    let imm16 = (first & 0xf) << 12 | (first & 0x400) << 1 | (second & 0x7000) >> 4 | second & 0xff;
+
+    if first & 0xfbf0 == 0xf240 {
+        // Decode movw rd, #imm16.
+        Effect::Load { register: rd, value: imm16 as u64, derived: false }
+    } else if first & 0xfbf0 == 0xf2c0 {
+        // Decode movt rd, #imm16.
+        Effect::load(rd, registers[rd].map(|low| (imm16 as u64) << 16 | low & 0xffff), true)
+    } else if first & 0xff7f == 0xf85f {
+        // Decode ldr.w rt, [pc, #±imm12], which loads the constant from the literal pool.
+        let displacement = second & 0xfff;
+
+        let address = if first & (1 << 7) != 0 {
+            aligned_pc.wrapping_add(displacement)
+        } else {
+            aligned_pc.wrapping_sub(displacement)
         };
 
-        // Track the value and distance score.
-        scores.insert(offset.abs_diff(closest), value);
+        Effect::load(rt, load_u32_le(bytes, mapping, address).map(|value| value as u64), false)
+    } else if first & 0xf800 == 0xf000 && second & 0x8000 != 0 {
+        // Decode b.w label and bl label, along with the other branch and control instructions.
+        Effect::Branch
+    } else if first & 0xfff0 == 0xe8d0 && second & 0xffe0 == 0xf000 {
+        // Decode tbb [rn, rm] and tbh [rn, rm, lsl #1].
+        Effect::Branch
+    } else if first & 0xffd0 == 0xe890 || first & 0xffd0 == 0xe910 {
+        // Decode ldm.w rn, {registers} and ldmdb rn, {registers}, which return if they load the
+        // program counter.
+        match second & 0x8000 {
+            0 => Effect::Clobber(second),
+            _ => Effect::Branch,
+        }
+    } else if first & 0xfe50 == 0xe850 {
+        // Decode ldrd rt, rt2, [rn] and the exclusive loads.
+        Effect::Clobber(1 << rt | 1 << rd)
+    } else if first & 0xfe10 == 0xf810 {
+        // Decode the loads, where loading a word into the program counter branches, and loading
+        // anything else into it is a preload hint.
+        match (rt, (first >> 5) & 0x3) {
+            (15, 2) => Effect::Branch,
+            (15, _) => Effect::None,
+            _ => Effect::Clobber(1 << rt),
+        }
+    } else if first & 0xf800 == 0xf000 || first & 0xfe00 == 0xea00 || first & 0xfe00 == 0xfa00 {
+        // Decode the data-processing instructions. The comparisons encode the program counter as
+        // the destination.
+        match rd {
+            15 => Effect::None,
+            _ => Effect::Clobber(1 << rd),
+        }
+    } else {
+        Effect::None
     }
+}
 
-    scores
+/// Decodes the Thumb instruction at the given offset with the program counter `pc`, given the
+/// current values of the registers `registers`.
+///
+/// Yields the size and the effect of the instruction, or `None` if it lies outside of the byte
+/// array.
+fn decode_thumb(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    offset: usize,
+    pc: u64,
+    registers: &Registers,
+) -> Option<(usize, Effect)> {
+    let first = u16::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?]) as u32;
+
+    // Reading the program counter yields the address of the instruction plus 4, and is aligned to
+    // 4 bytes for loads.
+    let pc = (pc as u32).wrapping_add(4);
+    let aligned_pc = pc & !3;
+
+    // Check if this is a 32-bit instruction.
+    if first >> 11 >= 0b11101 {
+        let second = u16::from_le_bytes([*bytes.get(offset + 2)?, *bytes.get(offset + 3)?]) as u32;
+
+        return Some((4, decode_thumb32(bytes, mapping, (first, second), aligned_pc, registers)));
+    }
+
+    let low = (first & 0x7) as usize;
+    let high = ((first >> 8) & 0x7) as usize;
+    let loads = first & (1 << 11) != 0;
+
+    let effect = if first & 0xf800 == 0x4800 {
+        // Decode ldr rt, [pc, #imm8 * 4], which loads the constant from the literal pool.
+        let address = aligned_pc.wrapping_add((first & 0xff) << 2);
+
+        Effect::load(high, load_u32_le(bytes, mapping, address).map(|value| value as u64), false)
+    } else if first & 0xff78 == 0x4478 {
+        // Decode add rdn, pc, which turns a PC-relative offset into an address.
+        let rdn = ((first >> 4) & 0x8 | first & 0x7) as usize;
+        let value = registers[rdn].map(|value| (value as u32).wrapping_add(pc) as u64);
+
+        Effect::load(rdn, value, true)
+    } else if first & 0xf800 == 0xa000 {
+        // Decode adr rd, label, i.e. add rd, pc, #imm8 * 4.
+        let value = aligned_pc.wrapping_add((first & 0xff) << 2);
+
+        Effect::Load { register: high, value: value as u64, derived: false }
+    } else if first & 0xfc00 == 0x4400 {
+        // Decode the instructions that operate on the high registers.
+        let rdn = ((first >> 4) & 0x8 | first & 0x7) as usize;
+        let rm = ((first >> 3) & 0xf) as usize;
+
+        match (first >> 8) & 0x3 {
+            // Decode bx rm and blx rm.
+            3 => Effect::Branch,
+            // Decode cmp rn, rm.
+            1 => Effect::None,
+            _ if rdn == 15 => Effect::Branch,
+            // Decode mov rd, rm, which copies the value.
+            2 => Effect::load(rdn, registers[rm], false),
+            _ => Effect::Clobber(1 << rdn),
+        }
+    } else if first & 0xe000 == 0x0000 {
+        // Decode the shifts and the additions and subtractions of the low registers.
+        Effect::Clobber(1 << low)
+    } else if first & 0xe000 == 0x2000 {
+        // Decode movs rd, #imm8 and the other instructions with an 8-bit immediate.
+        match (first >> 11) & 0x3 {
+            0 => Effect::Load { register: high, value: (first & 0xff) as u64, derived: false },
+            1 => Effect::None,
+            _ => Effect::Clobber(1 << high),
+        }
+    } else if first & 0xfc00 == 0x4000 {
+        // Decode the data-processing instructions, of which tst, cmp and cmn only set the flags.
+        match (first >> 6) & 0xf {
+            8 | 10 | 11 => Effect::None,
+            _ => Effect::Clobber(1 << low),
+        }
+    } else if first & 0xf000 == 0x5000 {
+        // Decode the transfers with a register offset, of which the first three are stores.
+        match (first >> 9) & 0x7 {
+            0..=2 => Effect::None,
+            _ => Effect::Clobber(1 << low),
+        }
+    } else if first & 0xe000 == 0x6000 || first & 0xf000 == 0x8000 {
+        // Decode the transfers with an immediate offset.
+        if loads {
+            Effect::Clobber(1 << low)
+        } else {
+            Effect::None
+        }
+    } else if first & 0xf000 == 0x9000 {
+        // Decode the transfers relative to the stack pointer.
+        if loads {
+            Effect::Clobber(1 << high)
+        } else {
+            Effect::None
+        }
+    } else if first & 0xf000 == 0xa000 {
+        // Decode add rd, sp, #imm8 * 4.
+        Effect::Clobber(1 << high)
+    } else if first & 0xfe00 == 0xbc00 {
+        // Decode pop {registers}, which returns if it pops the program counter.
+        match first & 0x100 {
+            0 => Effect::Clobber(first & 0xff),
+            _ => Effect::Branch,
+        }
+    } else if first & 0xf500 == 0xb100 {
+        // Decode cbz rn, label and cbnz rn, label.
+        Effect::Branch
+    } else if first & 0xff00 == 0xb200 || first & 0xff00 == 0xba00 {
+        // Decode the extensions and the byte reversals.
+        Effect::Clobber(1 << low)
+    } else if first & 0xf000 == 0xc000 {
+        // Decode ldm rn!, {registers}.
+        if loads {
+            Effect::Clobber(first & 0xff)
+        } else {
+            Effect::None
+        }
+    } else if first & 0xf000 == 0xd000 {
+        // Decode the conditional branches, sharing their encoding with udf and svc.
+        match (first >> 8) & 0xf {
+            0xe | 0xf => Effect::None,
+            _ => Effect::Branch,
+        }
+    } else if first & 0xf800 == 0xe000 {
+        // Decode b label.
+        Effect::Branch
+    } else {
+        Effect::None
+    };
+
+    Some((2, effect))
+}
+
+/// Decodes the A32 or Thumb instruction at the given offset with the program counter `pc`, given
+/// the current values of the registers `registers`.
+///
+/// Yields the size and the effect of the instruction, or `None` if it lies outside of the byte
+/// array.
+fn decode_arm(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    thumb: bool,
+    offset: usize,
+    pc: u64,
+    registers: &Registers,
+) -> Option<(usize, Effect)> {
+    match thumb {
+        true => decode_thumb(bytes, mapping, offset, pc, registers),
+        _ => Some((4, decode_a32(bytes, mapping, offset, pc, registers)?)),
+    }
+}
+
+pub fn find_blobs_arm(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    let mut known_offsets = BTreeSet::new();
+    let mut constants = vec![];
+
+    // As we don't know whether the code uses the ARM or the Thumb instruction set, we simply try
+    // both.
+    for thumb in [false, true] {
+        // The tree, names and data are passed in r1, r2 and r3.
+        let decode = |offset, pc, registers: &Registers| decode_arm(bytes, mapping, thumb, offset, pc, registers);
+
+        let (offsets, addresses) = track_registers(mapping, [None; 32], (1, 2, 3), tree_offset, name_offset, decode);
+
+        known_offsets.extend(offsets);
+        constants.extend(addresses);
+    }
+
+    // Now that we have a set of known offsets, we can try and find the instruction loading the
+    // blob offset.
+    score_constants(known_offsets, constants)
+}
+
+/// Decodes the 32-bit word at the given offset in the given byte order.
+fn read_u32(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let mut slice = [0u8; 4];
+    slice.copy_from_slice(bytes.get(offset..)?.get(..4)?);
+
+    if little_endian {
+        Some(u32::from_le_bytes(slice))
+    } else {
+        Some(u32::from_be_bytes(slice))
+    }
+}
+
+/// Decodes the 64-bit word at the given offset in the given byte order.
+fn read_u64(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u64> {
+    let mut slice = [0u8; 8];
+    slice.copy_from_slice(bytes.get(offset..)?.get(..8)?);
+
+    if little_endian {
+        Some(u64::from_le_bytes(slice))
+    } else {
+        Some(u64::from_be_bytes(slice))
+    }
+}
+
+/// Calculates the file offset from the virtual address `value`. 32-bit code may have
+/// sign-extended the address, in which case we retry with the lower 32 bits.
+fn resolve_address(mapping: &ExecutableMapping, value: u64) -> Option<usize> {
+    mapping
+        .rva_to_file_offset(value as usize)
+        .or_else(|| mapping.rva_to_file_offset(value as u32 as usize))
+}
+
+/// Sweeps over the instructions in the file range `range` of the mapping `mapping`, starting out
+/// with the register values `pinned`. Registers that start out with a value, such as the zero
+/// register or the global pointer, are never overwritten. Every instruction is decoded by
+/// `decode`, given its file offset, its virtual address and the current values of the registers,
+/// which yields the size and the effect of the instruction. Registers lose their value whenever
+/// they are written with anything but a constant, and all of them do so once control is
+/// transferred elsewhere.
+///
+/// Calls `visit` with the file offset of every instruction loading a constant, the register, the
+/// constant, and whether the constant was derived from the previous value of that register.
+fn sweep_registers<F, V>(
+    mapping: &ExecutableMapping,
+    pinned: Registers,
+    range: Range<usize>,
+    mut decode: F,
+    mut visit: V,
+) where
+    F: FnMut(usize, u64, &Registers) -> Option<(usize, Effect)>,
+    V: FnMut(usize, usize, u64, bool),
+{
+    let mut registers = pinned;
+    let mut delay_slot = false;
+    let mut leaving = false;
+    let mut offset = range.start;
+
+    while offset < range.end {
+        // Control leaves once the instruction in the delay slot of a branch has executed.
+        if std::mem::take(&mut leaving) {
+            registers = pinned;
+        }
+
+        let pc = match mapping.file_offset_to_rva(offset) {
+            Some(pc) => pc as u64,
+            _ => break,
+        };
+
+        let (size, effect) = match decode(offset, pc, &registers) {
+            Some(decoded) => decoded,
+            _ => break,
+        };
+
+        let instruction_offset = offset;
+        offset += size;
+        leaving = std::mem::take(&mut delay_slot);
+
+        match effect {
+            Effect::Load { register, value, derived } if pinned[register].is_none() => {
+                registers[register] = Some(value);
+                visit(instruction_offset, register, value, derived);
+            }
+            Effect::Clobber(mask) => {
+                for (register, value) in registers.iter_mut().enumerate() {
+                    if mask & (1 << register) != 0 && pinned[register].is_none() {
+                        *value = None;
+                    }
+                }
+            }
+            Effect::Branch => registers = pinned,
+            Effect::DelayedBranch => delay_slot = true,
+            _ => (),
+        }
+    }
+}
+
+/// Tracks the constants that are loaded into the registers while sweeping over the code ranges of
+/// the mapping `mapping` like `sweep_registers`.
+///
+/// Yields the file offsets of the instructions loading the tree offset or the name offset into
+/// their argument registers, as well as the instructions loading other addresses into the data
+/// argument register together with those addresses.
+fn track_registers<F>(
+    mapping: &ExecutableMapping,
+    pinned: Registers,
+    (tree_reg, name_reg, blob_reg): (usize, usize, usize),
+    tree_offset: usize,
+    name_offset: usize,
+    mut decode: F,
+) -> (BTreeSet<usize>, Vec<(usize, usize)>)
+where
+    F: FnMut(usize, u64, &Registers) -> Option<(usize, Effect)>,
+{
+    let mut known_offsets = BTreeSet::new();
+    let mut constants: Vec<Option<(usize, usize)>> = vec![];
+
+    for code_range in mapping.code_ranges() {
+        let mut last_constant = None;
+
+        sweep_registers(mapping, pinned, code_range, &mut decode, |instruction_offset, register, value, derived| {
+            // The previous value of the data register was only an intermediate value, e.g. the
+            // upper half of the address, if the instruction closely follows the one loading it.
+            if register == blob_reg {
+                match last_constant.take() {
+                    Some((index, offset)) if derived && instruction_offset - offset <= 64 => constants[index] = None,
+                    _ => (),
+                }
+            }
+
+            // Look up the file offset.
+            let value = match resolve_address(mapping, value) {
+                Some(value) => value,
+                _ => return,
+            };
+
+            // Check if we found a constant with the right tree offset or name offset.
+            if (register == tree_reg && value == tree_offset) || (register == name_reg && value == name_offset) {
+                known_offsets.insert(instruction_offset);
+            } else if register == blob_reg {
+                last_constant = Some((constants.len(), instruction_offset));
+                constants.push(Some((instruction_offset, value)));
+            }
+        });
+    }
+
+    (known_offsets, constants.into_iter().flatten().collect())
+}
+
+/// Decodes the compressed RISC-V instruction `instruction`, given the current values of the
+/// registers `registers`.
+///
+/// Yields the effect of the instruction.
+fn decode_riscv_compressed(instruction: u64, registers: &Registers) -> Effect {
+    let rd = ((instruction >> 7) & 0x1f) as usize;
+    let rs2 = ((instruction >> 2) & 0x1f) as usize;
+    let immediate = sign_extend((instruction >> 7) & 0x20 | (instruction >> 2) & 0x1f, 6);
+
+    match (instruction & 0x3, instruction >> 13) {
+        // Decode c.addi4spn rd', nzuimm and the loads, which write rd' in bits 2 to 4.
+        (0, 0..=3) => Effect::Clobber(1 << (8 + rs2 % 8)),
+        // Decode c.addi rd, imm.
+        (1, 0) => Effect::load(rd, registers[rd].map(|value| value.wrapping_add(immediate)), true),
+        // Decode c.addiw rd, imm, which shares its encoding with c.jal label on 32-bit.
+        (1, 1) => Effect::Clobber(1 << rd),
+        // Decode c.li rd, imm.
+        (1, 2) => Effect::Load { register: rd, value: immediate, derived: false },
+        // Decode c.addi16sp imm, which adjusts the stack pointer.
+        (1, 3) if rd == 2 => Effect::Clobber(1 << 2),
+        // Decode c.lui rd, imm.
+        (1, 3) => Effect::Load { register: rd, value: immediate << 12, derived: false },
+        // Decode the arithmetic instructions, which write rd' in bits 7 to 9.
+        (1, 4) => Effect::Clobber(1 << (8 + rd % 8)),
+        // Decode c.j label, c.beqz rs1', label and c.bnez rs1', label.
+        (1, 5..=7) => Effect::Branch,
+        // Decode c.slli rd, shamt and the loads relative to the stack pointer.
+        (2, 0..=3) => Effect::Clobber(1 << rd),
+        // Decode c.jr rs1, c.jalr rs1 and c.ebreak.
+        (2, 4) if rs2 == 0 => Effect::Branch,
+        // Decode c.mv rd, rs2, which copies the value.
+        (2, 4) if instruction & (1 << 12) == 0 => Effect::load(rd, registers[rs2], false),
+        // Decode c.add rd, rs2.
+        (2, 4) => Effect::Clobber(1 << rd),
+        _ => Effect::None,
+    }
+}
+
+/// Decodes the RISC-V instruction at the given offset with the program counter `pc`, given the
+/// current values of the registers `registers`.
+///
+/// Yields the size and the effect of the instruction, or `None` if it lies outside of the byte
+/// array.
+fn decode_riscv(
+    bytes: &[u8],
+    offset: usize,
+    pc: u64,
+    registers: &Registers,
+) -> Option<(usize, Effect)> {
+    // Compressed instructions are 16 bits in size.
+    if *bytes.get(offset)? & 0x3 != 0x3 {
+        let instruction = u16::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?]) as u64;
+
+        return Some((2, decode_riscv_compressed(instruction, registers)));
+    }
+
+    let instruction = read_u32_le(bytes, offset)? as u64;
+    let rd = ((instruction >> 7) & 0x1f) as usize;
+    let rs1 = ((instruction >> 15) & 0x1f) as usize;
+    let upper = sign_extend(instruction & 0xffff_f000, 32);
+
+    let effect = match instruction & 0x7f {
+        // Decode auipc rd, imm20.
+        0x17 => Effect::Load { register: rd, value: pc.wrapping_add(upper), derived: false },
+        // Decode lui rd, imm20.
+        0x37 => Effect::Load { register: rd, value: upper, derived: false },
+        // Decode addi rd, rs1, imm12.
+        0x13 if (instruction >> 12) & 0x7 == 0 => {
+            let immediate = sign_extend(instruction >> 20, 12);
+
+            Effect::load(rd, registers[rs1].map(|value| value.wrapping_add(immediate)), rs1 == rd)
+        }
+        // Decode the loads, the atomics and the other arithmetic instructions, as well as the
+        // floating-point instructions, some of which write rd.
+        0x03 | 0x13 | 0x1b | 0x2f | 0x33 | 0x3b | 0x53 => Effect::Clobber(1 << rd),
+        // Decode the CSR instructions.
+        0x73 if (instruction >> 12) & 0x7 != 0 => Effect::Clobber(1 << rd),
+        // Decode the conditional branches, jal label and jalr rd, rs1, imm12.
+        0x63 | 0x67 | 0x6f => Effect::Branch,
+        _ => Effect::None,
+    };
+
+    Some((4, effect))
+}
+
+/// Yields the initial values of the RISC-V registers, where the zero register x0 always reads as
+/// zero, and the global pointer is held by x3.
+fn riscv_registers(mapping: &ExecutableMapping) -> Registers {
+    let mut registers = [None; 32];
+    registers[0] = Some(0);
+    registers[3] = mapping.global_pointer().map(|address| address as u64);
+
+    registers
+}
+
+pub fn find_blobs_riscv(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    // The tree, names and data are passed in a1, a2 and a3, i.e. x11, x12 and x13.
+    let decode = |offset, pc, registers: &Registers| decode_riscv(bytes, offset, pc, registers);
+    let (known_offsets, constants) = track_registers(mapping, riscv_registers(mapping), (11, 12, 13), tree_offset, name_offset, decode);
+
+    // Now that we have a set of known offsets, we can try and find the addi instruction
+    // referencing the blob offset.
+    score_constants(known_offsets, constants)
+}
+
+/// Decodes the MIPS instruction at the given offset in the given byte order, given the current
+/// values of the registers `registers`.
+///
+/// Yields the effect of the instruction, or `None` if it lies outside of the byte array.
+fn decode_mips(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    offset: usize,
+    little_endian: bool,
+    registers: &Registers,
+) -> Option<Effect> {
+    let instruction = read_u32(bytes, offset, little_endian)? as u64;
+    let rs = ((instruction >> 21) & 0x1f) as usize;
+    let rt = ((instruction >> 16) & 0x1f) as usize;
+    let rd = ((instruction >> 11) & 0x1f) as usize;
+    let immediate = sign_extend(instruction & 0xffff, 16);
+
+    // Looks up the slot at the given offset from rs, e.g. in the GOT through the global pointer.
+    let slot = || resolve_address(mapping, registers[rs]?.wrapping_add(immediate));
+
+    let effect = match instruction >> 26 {
+        // Decode lui rt, imm16.
+        0x0f => Effect::Load { register: rt, value: sign_extend((instruction & 0xffff) << 16, 32), derived: false },
+        // Decode addiu rt, rs, imm16 and daddiu rt, rs, imm16.
+        0x09 | 0x19 => Effect::load(rt, registers[rs].map(|value| value.wrapping_add(immediate)), rs == rt),
+        // Decode ori rt, rs, uimm16, which forms absolute addresses together with lui.
+        0x0d => Effect::load(rt, registers[rs].map(|value| value | instruction & 0xffff), rs == rt),
+        // Decode lw rt, imm16(rs), which loads the address from the GOT through the global
+        // pointer.
+        0x23 => {
+            let value = slot().and_then(|slot| read_u32(bytes, slot, little_endian));
+
+            Effect::load(rt, value.map(|value| sign_extend(value as u64, 32)), false)
+        }
+        // Decode ld rt, imm16(rs), the 64-bit equivalent.
+        0x37 => Effect::load(rt, slot().and_then(|slot| read_u64(bytes, slot, little_endian)), false),
+        // Decode jr rs and jalr rd, rs.
+        0x00 if matches!(instruction & 0x3f, 0x08 | 0x09) => Effect::DelayedBranch,
+        // Decode the other register instructions, apart from those writing HI and LO or trapping.
+        0x00 if matches!(instruction & 0x3f, 0x0c | 0x0d | 0x18..=0x1f) => Effect::None,
+        0x00 | 0x1c => Effect::Clobber(1 << rd),
+        // Decode the instructions that write either rt or rd, such as ext and seb.
+        0x1f => Effect::Clobber(1 << rt | 1 << rd),
+        // Decode the branches, j label and jal label.
+        0x01..=0x07 | 0x14..=0x17 => Effect::DelayedBranch,
+        // Decode the other instructions with an immediate, and the loads.
+        0x08 | 0x0a..=0x0c | 0x0e | 0x18 | 0x1a | 0x1b | 0x20..=0x27 | 0x30 | 0x34 => Effect::Clobber(1 << rt),
+        _ => Effect::None,
+    };
+
+    Some(effect)
+}
+
+/// Yields the initial values of the MIPS registers, where the zero register $0 always reads as
+/// zero, and the global pointer is held by $28.
+fn mips_registers(mapping: &ExecutableMapping) -> Registers {
+    let mut registers = [None; 32];
+    registers[0] = Some(0);
+    registers[28] = mapping.global_pointer().map(|address| address as u64);
+
+    registers
+}
+
+pub fn find_blobs_mips(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    let mut known_offsets = BTreeSet::new();
+    let mut constants = vec![];
+
+    // We can only trust the byte order if we know the architecture, so raw images are decoded in
+    // both byte orders.
+    let byte_orders = match mapping.architecture() {
+        Architecture::Unknown => vec![true, false],
+        _ => vec![mapping.is_little_endian()],
+    };
+
+    for little_endian in byte_orders {
+        // The tree, names and data are passed in $a1, $a2 and $a3, i.e. $5, $6 and $7.
+        let decode = |offset, _, registers: &Registers| {
+            Some((4, decode_mips(bytes, mapping, offset, little_endian, registers)?))
+        };
+
+        let (offsets, addresses) = track_registers(mapping, mips_registers(mapping), (5, 6, 7), tree_offset, name_offset, decode);
+
+        known_offsets.extend(offsets);
+        constants.extend(addresses);
+    }
+
+    // Now that we have a set of known offsets, we can try and find the addiu instruction
+    // referencing the blob offset.
+    score_constants(known_offsets, constants)
+}
+
+/// The extended opcodes of the PowerPC instructions with primary opcode 31 that write ra instead
+/// of rd, i.e. the logical operations, the shifts and the sign extensions.
+const PPC_WRITES_RA: [u64; 22] = [24, 26, 27, 28, 58, 60, 124, 284, 316, 412, 444, 476, 536, 539, 792, 794, 824, 826, 827, 922, 954, 986];
+
+/// The extended opcodes of the PowerPC instructions with primary opcode 31 that don't write any
+/// general-purpose register, i.e. the comparisons, the traps, the stores and the cache and
+/// special-purpose register management.
+const PPC_WRITES_NONE: [u64; 24] = [0, 4, 32, 54, 68, 86, 144, 146, 149, 150, 151, 178, 181, 183, 214, 215, 246, 247, 278, 407, 439, 467, 598, 982];
+
+/// Decodes the PowerPC instruction at the given offset in the given byte order, given the current
+/// values of the registers `registers`.
+///
+/// Yields the effect of the instruction, or `None` if it lies outside of the byte array.
+fn decode_ppc(
+    bytes: &[u8],
+    offset: usize,
+    little_endian: bool,
+    registers: &Registers,
+) -> Option<Effect> {
+    let instruction = read_u32(bytes, offset, little_endian)? as u64;
+    let rd = ((instruction >> 21) & 0x1f) as usize;
+    let ra = ((instruction >> 16) & 0x1f) as usize;
+    let rb = ((instruction >> 11) & 0x1f) as usize;
+    let immediate = sign_extend(instruction & 0xffff, 16);
+    let extended = (instruction >> 1) & 0x3ff;
+
+    let effect = match instruction >> 26 {
+        // Decode addi rd, ra, imm16, where r0 reads as zero, i.e. li rd, imm16.
+        14 if ra == 0 => Effect::Load { register: rd, value: immediate, derived: false },
+        14 => Effect::load(rd, registers[ra].map(|value| value.wrapping_add(immediate)), ra == rd),
+        // Decode addis rd, ra, imm16, where r0 reads as zero, i.e. lis rd, imm16.
+        15 if ra == 0 => Effect::Load { register: rd, value: immediate << 16, derived: false },
+        15 => Effect::load(rd, registers[ra].map(|value| value.wrapping_add(immediate << 16)), ra == rd),
+        // Decode ori ra, rs, uimm16, where rs is encoded in place of rd.
+        24 => Effect::load(ra, registers[rd].map(|value| value | instruction & 0xffff), ra == rd),
+        // Decode bc, b and bl, as well as bclr and bcctr, which includes blr.
+        16 | 18 => Effect::Branch,
+        19 if matches!(extended, 16 | 528) => Effect::Branch,
+        // Decode or ra, rs, rs, i.e. mr ra, rs, which copies the value.
+        31 if extended == 444 && rd == rb => Effect::load(ra, registers[rd], false),
+        31 if PPC_WRITES_RA.contains(&extended) => Effect::Clobber(1 << ra),
+        31 if PPC_WRITES_NONE.contains(&extended) => Effect::None,
+        31 => Effect::Clobber(1 << rd),
+        // Decode the other instructions with an immediate, of which the logical operations and
+        // the rotations write ra.
+        7 | 8 | 12 | 13 => Effect::Clobber(1 << rd),
+        20 | 21 | 23 | 25..=30 => Effect::Clobber(1 << ra),
+        // Decode the loads, where the odd opcodes update ra, and lmw, which loads rd to r31.
+        32 | 34 | 40 | 42 | 58 => Effect::Clobber(1 << rd),
+        33 | 35 | 41 | 43 => Effect::Clobber(1 << rd | 1 << ra),
+        46 => Effect::Clobber(u32::MAX << rd),
+        // Decode the stores that update ra.
+        37 | 39 | 45 | 53 | 55 => Effect::Clobber(1 << ra),
+        62 if instruction & 0x3 == 1 => Effect::Clobber(1 << ra),
+        _ => Effect::None,
+    };
+
+    Some(effect)
+}
+
+/// Yields the initial values of the PowerPC registers, where the TOC pointer is held by r2 on
+/// 64-bit PowerPC.
+fn ppc_registers(mapping: &ExecutableMapping) -> Registers {
+    let mut registers = [None; 32];
+    registers[2] = mapping.global_pointer().map(|address| address as u64);
+
+    registers
+}
+
+pub fn find_blobs_ppc(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    let mut known_offsets = BTreeSet::new();
+    let mut constants = vec![];
+
+    // We can only trust the byte order if we know the architecture, so raw images are decoded in
+    // both byte orders.
+    let byte_orders = match mapping.architecture() {
+        Architecture::Unknown => vec![false, true],
+        _ => vec![mapping.is_little_endian()],
+    };
+
+    for little_endian in byte_orders {
+        // The tree, names and data are passed in r4, r5 and r6.
+        let decode = |offset, _, registers: &Registers| {
+            Some((4, decode_ppc(bytes, offset, little_endian, registers)?))
+        };
+
+        let (offsets, addresses) = track_registers(mapping, ppc_registers(mapping), (4, 5, 6), tree_offset, name_offset, decode);
+
+        known_offsets.extend(offsets);
+        constants.extend(addresses);
+    }
+
+    // Now that we have a set of known offsets, we can try and find the addi instruction
+    // referencing the blob offset.
+    score_constants(known_offsets, constants)
+}
+
+pub fn find_blobs_relocations(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    let mut known_offsets = BTreeSet::new();
+    let code_ranges = mapping.code_ranges();
+
+    // Collect the addresses stored in the relocated slots outside of the code.
+    let mut constants = vec![];
+
+    for (address, size) in mapping.relocations() {
+        let slot = match mapping.rva_to_file_offset(address) {
+            Some(slot) if !code_ranges.iter().any(|code_range| code_range.contains(&slot)) => slot,
+            _ => continue,
+        };
+
+        // Decode the address stored in the slot.
+        let mut slice = [0u8; 8];
+
+        match bytes.get(slot..).and_then(|bytes| bytes.get(..size)) {
+            Some(pointer) => slice[..size].copy_from_slice(pointer),
+            _ => continue,
+        }
+
+        // Look up the file offset.
+        let value = match mapping.rva_to_file_offset(u64::from_le_bytes(slice) as usize) {
+            Some(value) => value,
+            _ => continue,
+        };
+
+        // Check if we found a slot with the right tree offset or name offset.
+        if value == tree_offset || value == name_offset {
+            known_offsets.insert(slot);
+        } else {
+            constants.push((slot, value));
+        }
+    }
+
+    // Now that we have a set of known offsets, we can try and find the slot referencing the blob
+    // offset.
+    score_constants(known_offsets, constants)
+}
+
+pub fn find_blobs_i32_const(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+    name_offset: usize,
+) -> BTreeMap<usize, usize> {
+    let mut known_offsets = BTreeSet::new();
+
+    // The instructions can only be found in the code section of a WebAssembly module.
+    let code_range = match wasm::code_section(bytes) {
+        Some(code_range) => code_range,
+        _ => return BTreeMap::new(),
+    };
+
+    // Collect the i32.const instructions and their operands.
+    let mut constants = vec![];
+    let code = &bytes[..code_range.end];
+
+    for offset in code_range {
+        // Look for the i32.const instruction.
+        if code[offset] != 0x41 {
+            continue;
+        }
+
+        // Decode the operand.
+        let mut end = offset + 1;
+
+        let value = match wasm::read_sleb128(code, &mut end) {
+            Some(value) => value as u32 as usize,
+            _ => continue,
+        };
+
+        // Look up the file offset.
+        let value = match mapping.rva_to_file_offset(value) {
+            Some(value) => value,
+            _ => continue,
+        };
+
+        // Check if we found an i32.const with the right offset.
+        if value == tree_offset || value == name_offset {
+            known_offsets.insert(offset);
+        } else {
+            constants.push((offset, value));
+        }
+    }
+
+    // Now that we have a set of known offsets, we can try and find the i32.const instruction
+    // referencing the blob offset.
+    score_constants(known_offsets, constants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The virtual address at which the test images are loaded.
+    const BASE: usize = 0x10000;
+
+    /// The file offsets of the data, the names and the tree in the test images.
+    const DATA_OFFSET: usize = 0x1000;
+    const NAME_OFFSET: usize = 0x1100;
+    const TREE_OFFSET: usize = 0x1200;
+
+    /// The file offset of an address that is loaded into the data register, but overwritten
+    /// before the registration function gets called.
+    const STALE_OFFSET: usize = 0x1850;
+
+    /// Builds an image loaded at `BASE` that starts with the given instructions.
+    fn image(instructions: &[u32], little_endian: bool) -> (Vec<u8>, ExecutableMapping) {
+        let mut bytes = vec![];
+
+        for instruction in instructions {
+            match little_endian {
+                true => bytes.extend_from_slice(&instruction.to_le_bytes()),
+                _ => bytes.extend_from_slice(&instruction.to_be_bytes()),
+            }
+        }
+
+        map_code(bytes)
+    }
+
+    /// Builds an image loaded at `BASE` that starts with the given code.
+    fn map_code(mut bytes: Vec<u8>) -> (Vec<u8>, ExecutableMapping) {
+        let code_size = bytes.len();
+        bytes.resize(0x2000, 0);
+
+        let mut mapping = ExecutableMapping::new();
+        mapping.insert(0..bytes.len(), BASE..BASE + bytes.len());
+        mapping.insert_code(0..code_size);
+
+        (bytes, mapping)
+    }
+
+    /// Yields the candidates for the data found by the given locator.
+    fn candidates(locator: Locator, (bytes, mapping): (Vec<u8>, ExecutableMapping)) -> Vec<usize> {
+        locator(&bytes, &mapping, TREE_OFFSET, NAME_OFFSET).into_values().collect()
+    }
+
+    #[test]
+    fn finds_references_of_every_architecture() {
+        let functions: [&[u32]; 3] = [
+            &[0xb000_0001, 0x9108_0021], // adrp x1, 0x11000 and add x1, x1, #0x200
+            &[0xe301_1200, 0xe340_1001], // movw r1, #0x1200 and movt r1, #1
+            &[0x0001_15b7, 0x2005_8593], // lui a1, 0x11 and addi a1, a1, 512
+        ];
+
+        for instructions in functions {
+            let (bytes, mapping) = image(instructions, true);
+
+            assert!(references(&bytes, &mapping, 0..8, TREE_OFFSET));
+            assert!(!references(&bytes, &mapping, 0..8, NAME_OFFSET));
+            assert!(!references(&bytes, &mapping, 4..8, TREE_OFFSET));
+        }
+    }
+
+    #[test]
+    fn accepts_relocated_pushes_only() {
+        let code = [
+            0x68, 0x50, 0x18, 0x01, 0x00, // push 0x11850
+            0x68, 0x00, 0x10, 0x01, 0x00, // push 0x11000
+            0x68, 0x00, 0x11, 0x01, 0x00, // push 0x11100
+            0x68, 0x00, 0x12, 0x01, 0x00, // push 0x11200
+        ];
+
+        // Every push is a candidate as long as we don't know which slots hold an address.
+        assert!(candidates(find_blobs_push, map_code(code.to_vec())).contains(&STALE_OFFSET));
+
+        // The immediate of the first push is just a number that happens to look like an address.
+        let (bytes, mut mapping) = map_code(code.to_vec());
+
+        for slot in [6, 11, 16] {
+            mapping.insert_relocation(BASE + slot, 4);
+        }
+
+        assert_eq!(candidates(find_blobs_push, (bytes, mapping)), vec![DATA_OFFSET]);
+    }
+
+    #[test]
+    fn finds_lea_of_both_calling_conventions() {
+        let code = [
+            0x48, 0x8d, 0x15, 0xf9, 0x11, 0x00, 0x00, // lea rdx, [rip + 0x11f9]
+            0x4c, 0x8d, 0x05, 0xf2, 0x10, 0x00, 0x00, // lea r8, [rip + 0x10f2]
+            0x4c, 0x8d, 0x0d, 0xeb, 0x0f, 0x00, 0x00, // lea r9, [rip + 0xfeb]
+            0x48, 0x8d, 0x0d, 0x34, 0x18, 0x00, 0x00, // lea rcx, [rip + 0x1834]
+        ];
+
+        // The Windows x64 calling convention passes the data in r9, not in rcx.
+        assert_eq!(candidates(find_blobs_lea, map_code(code.to_vec())), vec![DATA_OFFSET]);
+
+        let code = [
+            0x48, 0x8d, 0x35, 0xf9, 0x11, 0x00, 0x00, // lea rsi, [rip + 0x11f9]
+            0x48, 0x8d, 0x15, 0xf2, 0x10, 0x00, 0x00, // lea rdx, [rip + 0x10f2]
+            0x48, 0x8d, 0x0d, 0xeb, 0x0f, 0x00, 0x00, // lea rcx, [rip + 0xfeb]
+            0x4c, 0x8d, 0x0d, 0x34, 0x18, 0x00, 0x00, // lea r9, [rip + 0x1834]
+        ];
+
+        // The System V calling convention passes the data in rcx, not in r9.
+        assert_eq!(candidates(find_blobs_lea, map_code(code.to_vec())), vec![DATA_OFFSET]);
+
+        let code = [
+            0x48, 0x8d, 0x35, 0xf9, 0x11, 0x00, 0x00, // lea rsi, [rip + 0x11f9]
+            0x48, 0x8d, 0x15, 0xf2, 0x10, 0x00, 0x00, // lea rdx, [rip + 0x10f2]
+            0x4c, 0x8d, 0x0d, 0xeb, 0x0f, 0x00, 0x00, // lea r9, [rip + 0xfeb]
+        ];
+
+        // The tree and names are passed as in the System V calling convention, but the data as in
+        // the Windows x64 one.
+        assert!(candidates(find_blobs_lea, map_code(code.to_vec())).is_empty());
+    }
+
+    #[test]
+    fn finds_mov_immediates() {
+        let code = [
+            0xbe, 0x00, 0x12, 0x01, 0x00, // mov esi, 0x11200
+            0xba, 0x00, 0x11, 0x01, 0x00, // mov edx, 0x11100
+            0xb8, 0x50, 0x18, 0x01, 0x00, // mov eax, 0x11850
+            0xb9, 0x00, 0x10, 0x01, 0x00, // mov ecx, 0x11000
+        ];
+
+        // The address in eax is not passed as an argument.
+        assert_eq!(candidates(find_blobs_lea, map_code(code.to_vec())), vec![DATA_OFFSET]);
+    }
+
+    #[test]
+    fn finds_movs_to_stack_slots() {
+        let code = [
+            0xc7, 0x44, 0x24, 0x04, 0x00, 0x12, 0x01, 0x00, // mov dword ptr [esp + 4], 0x11200
+            0xc7, 0x44, 0x24, 0x08, 0x00, 0x11, 0x01, 0x00, // mov dword ptr [esp + 8], 0x11100
+            0xc7, 0x44, 0x24, 0x10, 0x50, 0x18, 0x01, 0x00, // mov dword ptr [esp + 16], 0x11850
+            0xc7, 0x44, 0x24, 0x0c, 0x00, 0x10, 0x01, 0x00, // mov dword ptr [esp + 12], 0x11000
+        ];
+
+        // The address stored at [esp + 16] is not passed as an argument.
+        assert_eq!(candidates(find_blobs_mov_stack, map_code(code.to_vec())), vec![DATA_OFFSET]);
+
+        // Without the tree and names, there is nothing to anchor the data to.
+        assert!(candidates(find_blobs_mov_stack, map_code(code[16..].to_vec())).is_empty());
+    }
+
+    #[test]
+    fn decodes_a64_instructions() {
+        let mut registers = [None; 32];
+        let decode = |instruction: u32, registers: &Registers| decode_a64(&instruction.to_le_bytes(), &ExecutableMapping::new(), 0, 0x1000, registers);
+
+        // adrp x3, 0x12000 and adr x3, 0x11010.
+        assert_eq!(decode(0xb000_0083, &registers), Some(Effect::Load { register: 3, value: 0x12000, derived: false }));
+        assert_eq!(decode(0x1008_0083, &registers), Some(Effect::Load { register: 3, value: 0x11010, derived: false }));
+
+        // add x3, x3, #0x850, add x3, x3, #1, lsl #12 and mov x3, x0.
+        assert_eq!(decode(0x9121_4063, &registers), Some(Effect::Clobber(1 << 3)));
+
+        registers[0] = Some(0x11200);
+        registers[3] = Some(0x11000);
+
+        assert_eq!(decode(0x9121_4063, &registers), Some(Effect::Load { register: 3, value: 0x11850, derived: true }));
+        assert_eq!(decode(0x9140_0463, &registers), Some(Effect::Load { register: 3, value: 0x12000, derived: true }));
+        assert_eq!(decode(0xaa00_03e3, &registers), Some(Effect::Load { register: 3, value: 0x11200, derived: false }));
+
+        // ldr x3, [sp, #8], ldr x3, label, ldp x2, x3, [sp], #16 and mov x3, #5.
+        assert_eq!(decode(0xf940_07e3, &registers), Some(Effect::Clobber(1 << 3)));
+        assert_eq!(decode(0x5808_0803, &registers), Some(Effect::Clobber(1 << 3)));
+        assert_eq!(decode(0xa8c1_0fe2, &registers), Some(Effect::Clobber(1 << 2 | 1 << 3 | 1 << 31)));
+        assert_eq!(decode(0xd280_00a3, &registers), Some(Effect::Clobber(1 << 3)));
+
+        // str x3, [sp, #8], stp x2, x3, [x4, #16]! and ldr q3, [x1], #16, which only write back
+        // the address.
+        assert_eq!(decode(0xf900_07e3, &registers), Some(Effect::None));
+        assert_eq!(decode(0xa981_0c82, &registers), Some(Effect::Clobber(1 << 4)));
+        assert_eq!(decode(0x3cc1_0423, &registers), Some(Effect::Clobber(1 << 1)));
+
+        // mrs x3, tpidr_el0, fmov x3, d0, nop and fadd d3, d3, d1.
+        assert_eq!(decode(0xd53b_d043, &registers), Some(Effect::Clobber(1 << 3)));
+        assert_eq!(decode(0x9e66_0003, &registers), Some(Effect::Clobber(1 << 3)));
+        assert_eq!(decode(0xd503_201f, &registers), Some(Effect::None));
+        assert_eq!(decode(0x1e61_2863, &registers), Some(Effect::None));
+
+        // bl label, cbz x3, label, b.eq label, blr x8 and ret.
+        for instruction in [0x9400_0000, 0xb400_0003, 0x5400_0000, 0xd63f_0100, 0xd65f_03c0] {
+            assert_eq!(decode(instruction, &registers), Some(Effect::Branch));
+        }
+    }
+
+    #[test]
+    fn finds_adrp_add_pairs() {
+        let instructions = [
+            0xb000_0001, // adrp x1, 0x11000
+            0x9108_0021, // add x1, x1, #0x200
+            0xb000_0002, // adrp x2, 0x11000
+            0x9104_0042, // add x2, x2, #0x100
+            0xb000_0003, // adrp x3, 0x11000
+            0x9400_0000, // bl label
+            0x9121_4063, // add x3, x3, #0x850
+            0xb000_0003, // adrp x3, 0x11000
+            0xf940_07e3, // ldr x3, [sp, #8]
+            0x9121_4063, // add x3, x3, #0x850
+            0xb000_0003, // adrp x3, 0x11000
+            0xaa00_03e3, // mov x3, x0
+            0x9121_4063, // add x3, x3, #0x850
+            0xb000_0003, // adrp x3, 0x11000
+            0x9100_0063, // add x3, x3, #0x0
+        ];
+
+        // The page of the stale address is lost to the call, to the load and to the copy.
+        let candidates = candidates(find_blobs_adrp, image(&instructions, true));
+
+        assert!(candidates.contains(&DATA_OFFSET));
+        assert!(!candidates.contains(&STALE_OFFSET));
+    }
+
+    #[test]
+    fn finds_adrp_ldr_pairs() {
+        let instructions = [
+            0xb000_0001, // adrp x1, 0x11000
+            0x9108_0021, // add x1, x1, #0x200
+            0xb000_0002, // adrp x2, 0x11000
+            0x9104_0042, // add x2, x2, #0x100
+            0xb000_0003, // adrp x3, 0x11000
+            0xf944_0063, // ldr x3, [x3, #0x800]
+        ];
+
+        // The GOT slot at 0x11800 holds the address of the data.
+        let (mut bytes, mapping) = image(&instructions, true);
+        bytes[0x1800..0x1808].copy_from_slice(&((BASE + DATA_OFFSET) as u64).to_le_bytes());
+
+        assert_eq!(candidates(find_blobs_adrp, (bytes, mapping)), vec![DATA_OFFSET]);
+
+        // Without the tree and names, there is nothing to anchor the data to.
+        assert!(candidates(find_blobs_adrp, image(&instructions[4..], true)).is_empty());
+    }
+
+    /// Decodes the given A32 instruction at 0x1000.
+    fn a32(instruction: u32, registers: &Registers) -> Effect {
+        decode_a32(&instruction.to_le_bytes(), &ExecutableMapping::new(), 0, 0x1000, registers).unwrap()
+    }
+
+    /// Decodes the Thumb instruction consisting of the given halfwords at 0x1000.
+    fn thumb(halfwords: &[u16], registers: &Registers) -> (usize, Effect) {
+        let bytes: Vec<u8> = halfwords.iter().flat_map(|halfword| halfword.to_le_bytes()).collect();
+
+        decode_thumb(&bytes, &ExecutableMapping::new(), 0, 0x1000, registers).unwrap()
+    }
+
+    #[test]
+    fn decodes_a32_instructions() {
+        let mut registers = [None; 32];
+
+        // movw r3, #0x1000 and movt r3, #1.
+        assert_eq!(a32(0xe301_3000, &registers), Effect::Load { register: 3, value: 0x1000, derived: false });
+        assert_eq!(a32(0xe340_3001, &registers), Effect::Clobber(1 << 3));
+
+        registers[3] = Some(0x1000);
+
+        assert_eq!(a32(0xe340_3001, &registers), Effect::Load { register: 3, value: 0x11000, derived: true });
+
+        // add r3, pc, r3, where the program counter reads as 0x1008.
+        assert_eq!(a32(0xe08f_3003, &registers), Effect::Load { register: 3, value: 0x2008, derived: true });
+
+        // ldr r3, [sp, #4], mov r3, r0 and cmp r3, #1.
+        assert_eq!(a32(0xe59d_3004, &registers), Effect::Clobber(1 << 3));
+        assert_eq!(a32(0xe1a0_3000, &registers), Effect::Clobber(1 << 3));
+        assert_eq!(a32(0xe353_0001, &registers), Effect::None);
+
+        // bl label, bx lr and pop {r4, pc}.
+        assert_eq!(a32(0xeb00_0000, &registers), Effect::Branch);
+        assert_eq!(a32(0xe12f_ff1e, &registers), Effect::Branch);
+        assert_eq!(a32(0xe8bd_8010, &registers), Effect::Branch);
+    }
+
+    #[test]
+    fn decodes_thumb_instructions() {
+        let mut registers = [None; 32];
+
+        // movw r3, #0x1000 and movt r3, #1.
+        assert_eq!(thumb(&[0xf241, 0x0300], &registers), (4, Effect::Load { register: 3, value: 0x1000, derived: false }));
+        assert_eq!(thumb(&[0xf2c0, 0x0301], &registers), (4, Effect::Clobber(1 << 3)));
+
+        registers[1] = Some(0x11200);
+        registers[3] = Some(0x1000);
+
+        assert_eq!(thumb(&[0xf2c0, 0x0301], &registers), (4, Effect::Load { register: 3, value: 0x11000, derived: true }));
+
+        // add r3, pc, where the program counter reads as 0x1004.
+        assert_eq!(thumb(&[0x447b], &registers), (2, Effect::Load { register: 3, value: 0x2004, derived: true }));
+
+        // mov r3, r1 and movs r3, #7.
+        assert_eq!(thumb(&[0x460b], &registers), (2, Effect::Load { register: 3, value: 0x11200, derived: false }));
+        assert_eq!(thumb(&[0x2307], &registers), (2, Effect::Load { register: 3, value: 7, derived: false }));
+
+        // ldr r3, [sp, #4], ldr.w r3, [sp, #4], pop.w {r4, r5} and cmp r3, #1.
+        assert_eq!(thumb(&[0x9b01], &registers), (2, Effect::Clobber(1 << 3)));
+        assert_eq!(thumb(&[0xf8dd, 0x3004], &registers), (4, Effect::Clobber(1 << 3)));
+        assert_eq!(thumb(&[0xe8bd, 0x0030], &registers), (4, Effect::Clobber(1 << 4 | 1 << 5)));
+        assert_eq!(thumb(&[0x2b01], &registers), (2, Effect::None));
+
+        // bl label, pop {r4, pc} and cbz r3, label.
+        assert_eq!(thumb(&[0xf000, 0xf800], &registers), (4, Effect::Branch));
+        assert_eq!(thumb(&[0xbd10], &registers), (2, Effect::Branch));
+        assert_eq!(thumb(&[0xb143], &registers), (2, Effect::Branch));
+    }
+
+    #[test]
+    fn finds_movw_movt_pairs() {
+        let instructions = [
+            0xe301_1200, // movw r1, #0x1200
+            0xe340_1001, // movt r1, #1
+            0xe301_2100, // movw r2, #0x1100
+            0xe340_2001, // movt r2, #1
+            0xe301_3850, // movw r3, #0x1850
+            0xeb00_0000, // bl label
+            0xe340_3001, // movt r3, #1
+            0xe301_3000, // movw r3, #0x1000
+            0xe340_3001, // movt r3, #1
+        ];
+
+        // The lower half of the stale address doesn't survive the call.
+        let candidates = candidates(find_blobs_arm, image(&instructions, true));
+
+        assert!(candidates.contains(&DATA_OFFSET));
+        assert!(!candidates.contains(&STALE_OFFSET));
+    }
+
+    #[test]
+    fn decodes_riscv_instructions() {
+        let mut registers = [None; 32];
+        let decode = |instruction: u32, registers: &Registers| decode_riscv(&instruction.to_le_bytes(), 0, 0x1000, registers);
+
+        // auipc a1, 0x12345 and lui a3, 0x80000, whose immediate is sign-extended.
+        assert_eq!(decode(0x1234_5597, &registers), Some((4, Effect::Load { register: 11, value: 0x1234_6000, derived: false })));
+        assert_eq!(decode(0x8000_06b7, &registers), Some((4, Effect::Load { register: 13, value: 0xffff_ffff_8000_0000, derived: false })));
+
+        // addi a3, a3, -1968 and mv a3, a0.
+        assert_eq!(decode(0x8506_8693, &registers), Some((4, Effect::Clobber(1 << 13))));
+
+        registers[13] = Some(0x12000);
+
+        assert_eq!(decode(0x8506_8693, &registers), Some((4, Effect::Load { register: 13, value: 0x11850, derived: true })));
+        assert_eq!(decode(0x0005_0693, &registers), Some((4, Effect::Clobber(1 << 13))));
+
+        // ld a3, 8(sp), jal ra, label and ret.
+        assert_eq!(decode(0x0081_3683, &registers), Some((4, Effect::Clobber(1 << 13))));
+        assert_eq!(decode(0x0000_00ef, &registers), Some((4, Effect::Branch)));
+        assert_eq!(decode(0x0000_8067, &registers), Some((4, Effect::Branch)));
+
+        // c.lui a3, 17, c.addi a3, -16, c.li a3, -3, c.mv a3, a0 and c.j label.
+        registers[10] = Some(0x11200);
+
+        assert_eq!(decode(0x66c5, &registers), Some((2, Effect::Load { register: 13, value: 0x11000, derived: false })));
+        assert_eq!(decode(0x16c1, &registers), Some((2, Effect::Load { register: 13, value: 0x11ff0, derived: true })));
+        assert_eq!(decode(0x56f5, &registers), Some((2, Effect::Load { register: 13, value: (-3i64) as u64, derived: false })));
+        assert_eq!(decode(0x86aa, &registers), Some((2, Effect::Load { register: 13, value: 0x11200, derived: false })));
+        assert_eq!(decode(0xa001, &registers), Some((2, Effect::Branch)));
+    }
+
+    #[test]
+    fn finds_lui_addi_pairs() {
+        let instructions = [
+            0x0001_15b7, // lui a1, 0x11
+            0x2005_8593, // addi a1, a1, 512
+            0x0001_1637, // lui a2, 0x11
+            0x1006_0613, // addi a2, a2, 256
+            0x0001_26b7, // lui a3, 0x12
+            0x0000_00ef, // jal ra, label
+            0x8506_8693, // addi a3, a3, -1968
+            0x0001_26b7, // lui a3, 0x12
+            0x0005_0693, // mv a3, a0
+            0x8506_8693, // addi a3, a3, -1968
+            0x0001_16b7, // lui a3, 0x11
+            0x0006_8693, // addi a3, a3, 0
+        ];
+
+        // The upper half of the stale address is lost to the call and to the copy.
+        let candidates = candidates(find_blobs_riscv, image(&instructions, true));
+
+        assert!(candidates.contains(&DATA_OFFSET));
+        assert!(!candidates.contains(&STALE_OFFSET));
+    }
+
+    #[test]
+    fn decodes_mips_instructions() {
+        let mut registers = [None; 32];
+        let decode = |instruction: u32, registers: &Registers| {
+            decode_mips(&instruction.to_be_bytes(), &ExecutableMapping::new(), 0, false, registers)
+        };
+
+        // lui $7, 0x8000, whose immediate is sign-extended.
+        assert_eq!(decode(0x3c07_8000, &registers), Some(Effect::Load { register: 7, value: 0xffff_ffff_8000_0000, derived: false }));
+
+        // addiu $7, $7, 0x1850 and ori $7, $7, 0x1234.
+        assert_eq!(decode(0x24e7_1850, &registers), Some(Effect::Clobber(1 << 7)));
+
+        registers[7] = Some(0x10000);
+
+        assert_eq!(decode(0x24e7_1850, &registers), Some(Effect::Load { register: 7, value: 0x11850, derived: true }));
+        assert_eq!(decode(0x34e7_1234, &registers), Some(Effect::Load { register: 7, value: 0x11234, derived: true }));
+
+        // lw $7, 8($sp) and move $7, $4.
+        assert_eq!(decode(0x8fa7_0008, &registers), Some(Effect::Clobber(1 << 7)));
+        assert_eq!(decode(0x0080_3825, &registers), Some(Effect::Clobber(1 << 7)));
+
+        // jal label and jr $ra.
+        assert_eq!(decode(0x0c00_0000, &registers), Some(Effect::DelayedBranch));
+        assert_eq!(decode(0x03e0_0008, &registers), Some(Effect::DelayedBranch));
+    }
+
+    #[test]
+    fn finds_lui_addiu_pairs() {
+        let instructions = [
+            0x3c05_0001, // lui $5, 1
+            0x24a5_1200, // addiu $5, $5, 0x1200
+            0x3c06_0001, // lui $6, 1
+            0x24c6_1100, // addiu $6, $6, 0x1100
+            0x3c07_0001, // lui $7, 1
+            0x0c00_0000, // jal label
+            0x0000_0000, // nop
+            0x24e7_1850, // addiu $7, $7, 0x1850
+            0x3c07_0001, // lui $7, 1
+            0x0c00_0000, // jal label
+            0x24e7_1000, // addiu $7, $7, 0x1000
+        ];
+
+        // The instruction in the delay slot still executes before the call.
+        let candidates = candidates(find_blobs_mips, image(&instructions, false));
+
+        assert!(candidates.contains(&DATA_OFFSET));
+        assert!(!candidates.contains(&STALE_OFFSET));
+    }
+
+    #[test]
+    fn decodes_ppc_instructions() {
+        let mut registers = [None; 32];
+        let decode = |instruction: u32, registers: &Registers| decode_ppc(&instruction.to_be_bytes(), 0, false, registers);
+
+        // addis r4, r2, 1, which adds to the TOC pointer.
+        assert_eq!(decode(0x3c82_0001, &registers), Some(Effect::Clobber(1 << 4)));
+
+        registers[2] = Some(0x8000);
+        registers[3] = Some(0x11000);
+
+        assert_eq!(decode(0x3c82_0001, &registers), Some(Effect::Load { register: 4, value: 0x18000, derived: false }));
+
+        // mr r6, r3 and ori r6, r6, 0x1000.
+        assert_eq!(decode(0x7c66_1b78, &registers), Some(Effect::Load { register: 6, value: 0x11000, derived: false }));
+        assert_eq!(decode(0x60c6_1000, &registers), Some(Effect::Clobber(1 << 6)));
+
+        // lwz r6, 8(r1), mflr r0 and cmpw cr7, r6, r4.
+        assert_eq!(decode(0x80c1_0008, &registers), Some(Effect::Clobber(1 << 6)));
+        assert_eq!(decode(0x7c08_02a6, &registers), Some(Effect::Clobber(1 << 0)));
+        assert_eq!(decode(0x7f86_2000, &registers), Some(Effect::None));
+
+        // bl label and blr.
+        assert_eq!(decode(0x4800_0001, &registers), Some(Effect::Branch));
+        assert_eq!(decode(0x4e80_0020, &registers), Some(Effect::Branch));
+    }
+
+    #[test]
+    fn finds_lis_addi_pairs() {
+        let instructions = [
+            0x3c80_0001, // lis r4, 1
+            0x3884_1200, // addi r4, r4, 0x1200
+            0x3ca0_0001, // lis r5, 1
+            0x38a5_1100, // addi r5, r5, 0x1100
+            0x3cc0_0001, // lis r6, 1
+            0x4800_0001, // bl label
+            0x38c6_1850, // addi r6, r6, 0x1850
+            0x3cc0_0001, // lis r6, 1
+            0x7c66_1b78, // mr r6, r3
+            0x38c6_1850, // addi r6, r6, 0x1850
+            0x3cc0_0001, // lis r6, 1
+            0x60c6_1000, // ori r6, r6, 0x1000
+        ];
+
+        // The upper half of the stale address is lost to the call and to the copy.
+        let candidates = candidates(find_blobs_ppc, image(&instructions, false));
+
+        assert!(candidates.contains(&DATA_OFFSET));
+        assert!(!candidates.contains(&STALE_OFFSET));
+    }
 }
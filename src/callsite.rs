@@ -0,0 +1,311 @@
+use anyhow::Result;
+use iced_x86::{Code, FlowControl, Instruction, Mnemonic, OpKind, Register};
+use std::collections::{HashSet, VecDeque};
+
+use crate::blob;
+use crate::executable::{Architecture, ExecutableMapping};
+use crate::object;
+use crate::symbols::ResourceSymbols;
+
+/// The name of the function that registers the arrays emitted by rcc.
+const REGISTER_FUNCTION: &str = "qRegisterResourceData";
+
+/// The number of instructions before a call that are searched for its arguments.
+const WINDOW_SIZE: usize = 16;
+
+/// The argument registers of the System V calling convention.
+const SYSV_REGISTERS: [Register; 4] = [Register::RDI, Register::RSI, Register::RDX, Register::RCX];
+
+/// The argument registers of the Windows x64 calling convention.
+const WIN64_REGISTERS: [Register; 4] = [Register::RCX, Register::RDX, Register::R8, Register::R9];
+
+/// Recovers the arguments of a call that are passed in the registers `registers` from the
+/// instructions `window` leading up to the call. We walk backwards until we reach an instruction
+/// that changes the control flow, and only consider the last instruction that writes to each
+/// register.
+fn register_arguments(window: &VecDeque<Instruction>, registers: &[Register; 4]) -> [Option<u64>; 4] {
+    let mut arguments = [None; 4];
+    let mut seen = [false; 4];
+
+    for instruction in window.iter().rev() {
+        if instruction.flow_control() != FlowControl::Next {
+            break;
+        }
+
+        if instruction.op_count() == 0 || instruction.op0_kind() != OpKind::Register {
+            continue;
+        }
+
+        // Check if the instruction writes to one of the argument registers.
+        let register = instruction.op0_register().full_register();
+
+        let index = match registers.iter().position(|argument| *argument == register) {
+            Some(index) if !seen[index] => index,
+            _ => continue,
+        };
+
+        seen[index] = true;
+
+        arguments[index] = match instruction.mnemonic() {
+            Mnemonic::Lea => blob::memory_address(instruction),
+            Mnemonic::Mov => blob::immediate(instruction, 1),
+            _ => None,
+        };
+    }
+
+    arguments
+}
+
+/// Recovers the arguments of a call that are passed on the stack from the instructions `window`
+/// leading up to the call. The arguments are either pushed in reverse order, or stored relative
+/// to the stack pointer, e.g. `mov dword ptr [esp+4], imm32`.
+fn stack_arguments(window: &VecDeque<Instruction>) -> [Option<u64>; 4] {
+    let mut arguments = [None; 4];
+    let mut seen = [false; 4];
+    let mut pushes = 0;
+
+    for instruction in window.iter().rev() {
+        if instruction.flow_control() != FlowControl::Next {
+            break;
+        }
+
+        let (index, value) = match instruction.mnemonic() {
+            Mnemonic::Push => {
+                pushes += 1;
+                (pushes - 1, blob::immediate(instruction, 0))
+            }
+            Mnemonic::Mov if instruction.op0_kind() == OpKind::Memory => {
+                // Only consider stores relative to the stack pointer, before any pushes.
+                if pushes != 0 || instruction.memory_base() != Register::ESP || instruction.memory_index() != Register::None {
+                    continue;
+                }
+
+                let displacement = instruction.memory_displacement32() as usize;
+
+                if !displacement.is_multiple_of(4) {
+                    continue;
+                }
+
+                (displacement / 4, blob::immediate(instruction, 1))
+            }
+            _ => continue,
+        };
+
+        if index >= arguments.len() || seen[index] {
+            continue;
+        }
+
+        seen[index] = true;
+        arguments[index] = value;
+    }
+
+    arguments
+}
+
+/// Finds the stubs in the x86 or x86-64 image in the given byte array `bytes` that jump through
+/// the import slots `slots`, e.g. the PLT entries or the thunks.
+///
+/// Yields the virtual address of every stub.
+fn stub_addresses(bytes: &[u8], mapping: &ExecutableMapping, bitness: u32, slots: &HashSet<u64>) -> HashSet<u64> {
+    let mut stubs = HashSet::new();
+    let mut previous = Instruction::default();
+
+    blob::decode_x86(bytes, mapping, bitness, |_, instruction| {
+        if instruction.is_jmp_near_indirect() && blob::memory_address(instruction).is_some_and(|address| slots.contains(&address)) {
+            stubs.insert(instruction.ip());
+
+            // Stubs may start with an endbr instruction when CET is enabled.
+            if matches!(previous.code(), Code::Endbr32 | Code::Endbr64) && previous.next_ip() == instruction.ip() {
+                stubs.insert(previous.ip());
+            }
+        }
+
+        previous = *instruction;
+    });
+
+    stubs
+}
+
+/// Locates the calls to `qRegisterResourceData` in the x86 or x86-64 image in the given byte array
+/// `bytes`, either through its import slot, e.g. an entry of the GOT or the IAT, or through its
+/// definition. The four arguments of every call, being the version, the tree, the names and the
+/// data, are recovered from the instructions leading up to the call.
+///
+/// Yields the file offset of every call together with the arrays passed to it.
+pub fn find_registrations(bytes: &[u8], mapping: &ExecutableMapping) -> Result<Vec<(usize, ResourceSymbols)>> {
+    let mut registrations = vec![];
+
+    let bitness = match mapping.architecture() {
+        Architecture::X86 => 32,
+        Architecture::X86_64 => 64,
+        _ => return Ok(registrations),
+    };
+
+    let slots: HashSet<u64> = object::import_slots(bytes, mapping, REGISTER_FUNCTION)?
+        .into_iter()
+        .map(|slot| slot as u64)
+        .collect();
+
+    let mut targets: HashSet<u64> = object::function_addresses(bytes, mapping, REGISTER_FUNCTION)?
+        .into_iter()
+        .map(|address| address as u64)
+        .collect();
+
+    if slots.is_empty() && targets.is_empty() {
+        return Ok(registrations);
+    }
+
+    if !slots.is_empty() {
+        targets.extend(stub_addresses(bytes, mapping, bitness, &slots));
+    }
+
+    let mut tree_offsets = HashSet::new();
+    let mut window = VecDeque::with_capacity(WINDOW_SIZE);
+
+    blob::decode_x86(bytes, mapping, bitness, |offset, instruction| {
+        // Check if this is a call to the function, or a jump in case of a tail call.
+        let is_call = if instruction.is_call_near() || instruction.is_jmp_short_or_near() {
+            targets.contains(&instruction.near_branch_target())
+        } else if instruction.is_call_near_indirect() || instruction.is_jmp_near_indirect() {
+            blob::memory_address(instruction).is_some_and(|address| slots.contains(&address))
+        } else {
+            false
+        };
+
+        if is_call {
+            let arguments = if bitness == 32 {
+                stack_arguments(&window)
+            } else if mapping.is_pe() {
+                register_arguments(&window, &WIN64_REGISTERS)
+            } else {
+                register_arguments(&window, &SYSV_REGISTERS)
+            };
+
+            // Resolve the addresses of the arrays.
+            let [version, tree, names, data] = arguments;
+            let resolve = |address: Option<u64>| mapping.rva_to_file_offset(address? as usize);
+
+            let symbols = ResourceSymbols {
+                tree: resolve(tree),
+                names: resolve(names),
+                data: resolve(data),
+                version: version.map(|version| version as u32).filter(|version| (1..=3).contains(version)),
+            };
+
+            // We need at least the tree and the names.
+            if let (Some(tree_offset), Some(_)) = (symbols.tree, symbols.names) {
+                if tree_offsets.insert(tree_offset) {
+                    registrations.push((offset, symbols));
+                }
+            }
+        }
+
+        if window.len() == WINDOW_SIZE {
+            window.pop_front();
+        }
+
+        window.push_back(*instruction);
+    });
+
+    Ok(registrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_x86::{Decoder, DecoderOptions};
+
+    /// Decodes the given code at 0x1000 into the window of instructions leading up to a call.
+    fn window(code: &[u8], bitness: u32) -> VecDeque<Instruction> {
+        Decoder::with_ip(bitness, code, 0x1000, DecoderOptions::NONE).into_iter().collect()
+    }
+
+    #[test]
+    fn recovers_sysv_arguments() {
+        let code = [
+            0xbf, 0x03, 0x00, 0x00, 0x00, // mov edi, 3
+            0x48, 0x8d, 0x35, 0x00, 0x10, 0x00, 0x00, // lea rsi, [rip + 0x1000]
+            0x48, 0x8d, 0x15, 0x00, 0x10, 0x00, 0x00, // lea rdx, [rip + 0x1000]
+            0x48, 0x8d, 0x0d, 0x00, 0x10, 0x00, 0x00, // lea rcx, [rip + 0x1000]
+        ];
+
+        assert_eq!(register_arguments(&window(&code, 64), &SYSV_REGISTERS), [Some(3), Some(0x200c), Some(0x2013), Some(0x201a)]);
+    }
+
+    #[test]
+    fn recovers_win64_arguments() {
+        let code = [
+            0xb9, 0x03, 0x00, 0x00, 0x00, // mov ecx, 3
+            0x48, 0x8d, 0x15, 0x00, 0x10, 0x00, 0x00, // lea rdx, [rip + 0x1000]
+            0x4c, 0x8d, 0x05, 0x00, 0x10, 0x00, 0x00, // lea r8, [rip + 0x1000]
+            0x4c, 0x8d, 0x0d, 0x00, 0x10, 0x00, 0x00, // lea r9, [rip + 0x1000]
+        ];
+
+        assert_eq!(register_arguments(&window(&code, 64), &WIN64_REGISTERS), [Some(3), Some(0x200c), Some(0x2013), Some(0x201a)]);
+    }
+
+    #[test]
+    fn only_considers_the_last_writes_since_the_control_flow_changed() {
+        let code = [
+            0x48, 0x8d, 0x35, 0x00, 0x10, 0x00, 0x00, // lea rsi, [rip + 0x1000]
+            0xe8, 0x00, 0x00, 0x00, 0x00, // call 0x100c
+            0x48, 0x8d, 0x15, 0x00, 0x10, 0x00, 0x00, // lea rdx, [rip + 0x1000]
+            0x48, 0x8d, 0x15, 0x00, 0x20, 0x00, 0x00, // lea rdx, [rip + 0x2000]
+            0x48, 0x8d, 0x0d, 0x00, 0x10, 0x00, 0x00, // lea rcx, [rip + 0x1000]
+            0x48, 0x89, 0xc1, // mov rcx, rax
+        ];
+
+        assert_eq!(register_arguments(&window(&code, 64), &SYSV_REGISTERS), [None, None, Some(0x301a), None]);
+    }
+
+    #[test]
+    fn recovers_pushed_arguments() {
+        let code = [
+            0x68, 0x00, 0x30, 0x00, 0x00, // push 0x3000
+            0x68, 0x00, 0x20, 0x00, 0x00, // push 0x2000
+            0x68, 0x00, 0x10, 0x00, 0x00, // push 0x1000
+            0x6a, 0x03, // push 3
+        ];
+
+        // The arguments are pushed in reverse order.
+        assert_eq!(stack_arguments(&window(&code, 32)), [Some(3), Some(0x1000), Some(0x2000), Some(0x3000)]);
+
+        // The data is pushed before another call.
+        let mut code = code.to_vec();
+        code.splice(5..5, [0xe8, 0x00, 0x00, 0x00, 0x00]);
+
+        assert_eq!(stack_arguments(&window(&code, 32)), [Some(3), Some(0x1000), Some(0x2000), None]);
+    }
+
+    #[test]
+    fn recovers_stored_arguments() {
+        let code = [
+            0xc7, 0x44, 0x24, 0x0c, 0x00, 0x30, 0x00, 0x00, // mov dword ptr [esp + 12], 0x3000
+            0xc7, 0x44, 0x24, 0x08, 0x00, 0x20, 0x00, 0x00, // mov dword ptr [esp + 8], 0x2000
+            0xc7, 0x44, 0x24, 0x04, 0x00, 0x10, 0x00, 0x00, // mov dword ptr [esp + 4], 0x1000
+            0xc7, 0x04, 0x24, 0x03, 0x00, 0x00, 0x00, // mov dword ptr [esp], 3
+        ];
+
+        assert_eq!(stack_arguments(&window(&code, 32)), [Some(3), Some(0x1000), Some(0x2000), Some(0x3000)]);
+    }
+
+    #[test]
+    fn finds_stubs_jumping_through_import_slots() {
+        let code = [
+            0xf3, 0x0f, 0x1e, 0xfa, // endbr64
+            0xf2, 0xff, 0x25, 0xf5, 0x1f, 0x00, 0x00, // bnd jmp qword ptr [rip + 0x1ff5]
+            0x90, // nop
+            0xff, 0x25, 0xf6, 0x1f, 0x00, 0x00, // jmp qword ptr [rip + 0x1ff6]
+            0xff, 0x25, 0xf8, 0x1f, 0x00, 0x00, // jmp qword ptr [rip + 0x1ff8]
+        ];
+
+        let mut mapping = ExecutableMapping::new();
+        mapping.insert(0..code.len(), 0x1000..0x1000 + code.len());
+        mapping.insert_code(0..code.len());
+
+        // The stubs jump through the slots at 0x3000 and 0x3008, but not through 0x3010.
+        let slots = HashSet::from([0x3000, 0x3008]);
+
+        assert_eq!(stub_addresses(&code, &mapping, 64, &slots), HashSet::from([0x1000, 0x1004, 0x100c]));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::literal::{decode_escape, parse_integer, Dialect};
+use crate::symbols::ResourceSymbols;
+
+/// Skips whitespace and comments starting at the given offset. Yields the offset of the next
+/// token.
+fn skip_whitespace(source: &[u8], mut offset: usize) -> usize {
+    while offset < source.len() {
+        if source[offset].is_ascii_whitespace() {
+            offset += 1;
+        } else if source[offset..].starts_with(b"//") {
+            // Skip until the end of the line.
+            while offset < source.len() && source[offset] != b'\n' {
+                offset += 1;
+            }
+        } else if source[offset..].starts_with(b"/*") {
+            // Skip until the end of the comment.
+            offset = match source[offset + 2..].windows(2).position(|window| window == b"*/") {
+                Some(end) => offset + 2 + end + 2,
+                _ => source.len(),
+            };
+        } else {
+            break;
+        }
+    }
+
+    offset
+}
+
+/// Parses a list of integer literals of the form `{ 0x0, 0x1, ... }` starting at the given
+/// offset, which should point to the opening brace.
+fn parse_list(source: &[u8], mut offset: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+
+    // Skip the opening brace.
+    offset += 1;
+
+    loop {
+        offset = skip_whitespace(source, offset);
+
+        match source.get(offset)? {
+            b'}' => break,
+            b',' => {
+                offset += 1;
+                continue;
+            }
+            _ => (),
+        }
+
+        // Collect the literal.
+        let start = offset;
+
+        while offset < source.len() && source[offset].is_ascii_alphanumeric() {
+            offset += 1;
+        }
+
+        let literal = std::str::from_utf8(&source[start..offset]).ok()?;
+
+        if literal.is_empty() {
+            return None;
+        }
+
+        // Decode the literal, which is either hexadecimal, octal or decimal.
+        let value = u8::try_from(parse_integer(literal)?).ok()?;
+
+        bytes.push(value);
+    }
+
+    Some(bytes)
+}
+
+/// Parses a sequence of adjacent string literals of the form `"\x00\x01..." "..."` starting at the
+/// given offset, which should point to the opening quote of the first string literal. The escape
+/// sequences are decoded the way a C++ compiler would.
+fn parse_strings(source: &[u8], mut offset: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+
+    while source.get(offset) == Some(&b'"') {
+        // Skip the opening quote.
+        offset += 1;
+
+        loop {
+            let c = *source.get(offset)?;
+            offset += 1;
+
+            match c {
+                b'"' => break,
+                b'\\' => offset = decode_escape(source, offset, Dialect::Cpp, &mut bytes)?,
+                c => bytes.push(c),
+            }
+        }
+
+        offset = skip_whitespace(source, offset);
+    }
+
+    Some(bytes)
+}
+
+/// Looks up the definition of the array named `name` in the given source, and parses its
+/// initializer, which is either a list of integer literals or a sequence of string literals.
+fn parse_array(source: &[u8], name: &str) -> Option<Vec<u8>> {
+    let pattern = format!("{}[", name);
+
+    let mut offset = source
+        .windows(pattern.len())
+        .position(|window| window == pattern.as_bytes())?;
+
+    // Find the start of the initializer.
+    offset += source[offset..].iter().position(|c| *c == b'=')? + 1;
+    offset = skip_whitespace(source, offset);
+
+    match source.get(offset)? {
+        b'{' => parse_list(source, offset),
+        b'"' => parse_strings(source, offset),
+        _ => None,
+    }
+}
+
+/// Looks up the format version in the given source, which rcc stores as `int version = N;` in the
+/// function that registers the resources.
+fn parse_version(source: &str) -> Option<u32> {
+    let offset = source.find("int version = ")? + "int version = ".len();
+    let end = source[offset..].find(';')? + offset;
+
+    source[offset..end].trim().parse().ok()
+}
+
+/// Parses the C++ source generated by rcc (e.g. `qrc_*.cpp`) in the given byte array. Depending
+/// on the options passed to rcc, the arrays are initialized from lists of integer literals or from
+/// string literals; either way they are decoded and concatenated in the order of data, names and
+/// tree. If the data array is missing, its offset is left unset.
+///
+/// Yields the concatenated arrays and their offsets, or `None` if the tree or the names are
+/// missing.
+pub fn parse_source(bytes: &[u8]) -> Option<(Vec<u8>, ResourceSymbols)> {
+    let source = std::str::from_utf8(bytes).ok()?;
+
+    let tree = parse_array(bytes, "qt_resource_struct")?;
+    let names = parse_array(bytes, "qt_resource_name")?;
+    let data = parse_array(bytes, "qt_resource_data");
+    let data_len = data.as_ref().map_or(0, |data| data.len());
+
+    let symbols = ResourceSymbols {
+        data: data.as_ref().map(|_| 0),
+        names: Some(data_len),
+        tree: Some(data_len + names.len()),
+        version: parse_version(source),
+    };
+
+    Some(([data.unwrap_or_default(), names, tree].concat(), symbols))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_integer_lists() {
+        let source = b"static const unsigned char qt_resource_data[] = {\n  // comment\n  0x0,0x1f, 017 , 255, /* comment */ 0X10 };";
+
+        assert_eq!(parse_array(source, "qt_resource_data"), Some(vec![0, 0x1f, 0o17, 255, 0x10]));
+        assert_eq!(parse_list(b"{ 256 }", 0), None);
+        assert_eq!(parse_list(b"{ 0x1, ", 0), None);
+    }
+
+    #[test]
+    fn parses_string_literals() {
+        // Hexadecimal escape sequences are greedy, which is why rcc ends the literal after them.
+        let source = b"static const unsigned char qt_resource_name[] = \"\\x00\\x01\" \"a\\x1a\" \n \"\\0\\101\\n\";";
+
+        assert_eq!(parse_array(source, "qt_resource_name"), Some(vec![0, 1, b'a', 0x1a, 0, b'A', b'\n']));
+        assert_eq!(parse_strings(b"\"\\x\"", 0), None);
+        assert_eq!(parse_strings(b"\"abc", 0), None);
+    }
+
+    #[test]
+    fn leaves_missing_data_unset() {
+        let source = b"static const unsigned char qt_resource_struct[] = { 0x1, 0x2 };\n\
+            static const unsigned char qt_resource_name[] = { 0x3 };\n\
+            int version = 3;";
+
+        let (bytes, symbols) = parse_source(source).unwrap();
+
+        assert_eq!(bytes, vec![3, 1, 2]);
+        assert_eq!((symbols.data, symbols.names, symbols.tree, symbols.version), (None, Some(0), Some(1), Some(3)));
+    }
+}
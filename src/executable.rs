@@ -1,56 +1,348 @@
 use anyhow::Result;
 use goblin::Object;
-use goblin::elf::program_header::PT_LOAD;
-use rangemap::RangeMap;
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_MIPS, EM_PPC, EM_PPC64, EM_RISCV, EM_X86_64, ET_CORE};
+use goblin::elf::note::NT_FILE;
+use goblin::elf::program_header::{PF_X, PT_LOAD};
+use goblin::mach::Mach;
+use goblin::mach::constants::cputype::{get_arch_name_from_types, CPU_TYPE_ARM, CPU_TYPE_ARM64, CPU_TYPE_X86, CPU_TYPE_X86_64};
+use goblin::pe::header::{COFF_MACHINE_ARM64, COFF_MACHINE_ARMNT, COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+use goblin::pe::section_table::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE};
+use rangemap::{RangeMap, RangeSet};
 use std::collections::BTreeMap;
 use std::ops::Range;
 use std::path::PathBuf;
 
+use crate::wasm;
+
+/// Splits a universal (fat) Mach-O binary into its per-architecture slices.
+///
+/// Yields the name of the architecture and the file range of every slice, or an empty list if the
+/// given byte array is not a universal binary.
+pub fn fat_slices(bytes: &[u8]) -> Result<Vec<(String, Range<usize>)>> {
+    let mut slices = vec![];
+
+    let multi = match Object::parse(bytes)? {
+        Object::Mach(Mach::Fat(multi)) => multi,
+        _ => return Ok(slices),
+    };
+
+    for arch in multi.iter_arches() {
+        let arch = arch?;
+
+        // Look up the name of the architecture.
+        let name = match get_arch_name_from_types(arch.cputype(), arch.cpusubtype()) {
+            Some(name) => name.to_string(),
+            _ => format!("cpu_{:x}_{:x}", arch.cputype(), arch.cpusubtype()),
+        };
+
+        // Calculate the file range of this slice.
+        let start = arch.offset as usize;
+        let end = start + arch.size as usize;
+
+        slices.push((name, start..end));
+    }
+
+    Ok(slices)
+}
+
+/// Parses the `NT_FILE` note of an ELF core dump, which lists the files that were mapped into the
+/// address space of the process.
+///
+/// Yields the virtual address range and the path of every mapping.
+fn parse_file_note(
+    desc: &[u8],
+    is_64: bool,
+    little_endian: bool,
+) -> Vec<(Range<usize>, String)> {
+    let mut mappings = vec![];
+    let word_size = if is_64 { 8 } else { 4 };
+
+    // Decodes the word at the given index.
+    let word = |index: usize| -> Option<usize> {
+        let bytes = desc.get(index * word_size..)?.get(..word_size)?;
+        let mut slice = [0u8; 8];
+
+        let value = if little_endian {
+            slice[..word_size].copy_from_slice(bytes);
+            u64::from_le_bytes(slice)
+        } else {
+            slice[8 - word_size..].copy_from_slice(bytes);
+            u64::from_be_bytes(slice)
+        };
+
+        Some(value as usize)
+    };
+
+    // The note starts with the number of mappings and the page size, followed by the start
+    // address, end address and file offset of every mapping.
+    let count = match word(0) {
+        Some(count) => count,
+        _ => return mappings,
+    };
+
+    // The paths follow the table as NUL-terminated strings.
+    let start = match count.checked_mul(3).and_then(|words| (words + 2).checked_mul(word_size)) {
+        Some(start) if start <= desc.len() => start,
+        _ => return mappings,
+    };
+
+    let mut paths = desc[start..].split(|c| *c == 0);
+
+    for index in 0..count {
+        let (start, end) = match (word(2 + 3 * index), word(3 + 3 * index)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => break,
+        };
+
+        let path = match paths.next() {
+            Some(path) => String::from_utf8_lossy(path).to_string(),
+            _ => break,
+        };
+
+        mappings.push((start..end, path));
+    }
+
+    mappings
+}
+
+/// The base relocation types of the slots that hold 32-bit and 64-bit addresses respectively.
+const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
+const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+/// Parses the base relocation table of a PE image, which consists of blocks that each list the
+/// slots within a 4 KiB page that hold an absolute address.
+///
+/// Yields the relative virtual address and the size of every slot.
+fn parse_base_relocations(table: &[u8]) -> Vec<(usize, usize)> {
+    let mut relocations = vec![];
+    let mut offset = 0;
+
+    while offset + 8 <= table.len() {
+        // Decode the page address and the size of the block.
+        let mut slice = [0u8; 4];
+        slice.copy_from_slice(&table[offset..][..4]);
+        let page = u32::from_le_bytes(slice) as usize;
+
+        slice.copy_from_slice(&table[offset + 4..][..4]);
+        let size = u32::from_le_bytes(slice) as usize;
+
+        if size < 8 {
+            break;
+        }
+
+        let end = table.len().min(offset + size);
+
+        // Every entry consists of the type in the upper 4 bits and the offset within the page in
+        // the lower 12 bits.
+        for entry in table[offset + 8..end].chunks_exact(2) {
+            let entry = u16::from_le_bytes([entry[0], entry[1]]);
+
+            let slot_size = match entry >> 12 {
+                IMAGE_REL_BASED_HIGHLOW => 4,
+                IMAGE_REL_BASED_DIR64 => 8,
+                _ => continue,
+            };
+
+            relocations.push((page + (entry & 0xfff) as usize, slot_size));
+        }
+
+        offset += size;
+    }
+
+    relocations
+}
+
+/// Calculates the file range of `size` bytes at the file offset `offset`, clipped to the end of
+/// the given byte array, as the file may be truncated, which is common for core dumps.
+fn clip_file_range(bytes: &[u8], offset: u64, size: u64) -> Range<usize> {
+    let start = (offset as usize).min(bytes.len());
+    let end = start.saturating_add(size as usize).min(bytes.len());
+
+    start..end
+}
+
+/// The instruction set architecture of the code in an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Architecture {
+    Unknown,
+    X86,
+    X86_64,
+    Arm,
+    AArch64,
+    RiscV,
+    Mips,
+    PowerPC,
+    Wasm,
+}
+
+/// The value of the `initprot` field of Mach-O segments that are executable.
+const VM_PROT_EXECUTE: u32 = 4;
+
 pub struct ExecutableMapping {
+    /// The instruction set architecture.
+    architecture: Architecture,
+    /// Whether the image is a PE image, which uses the Windows calling conventions.
+    is_pe: bool,
+    /// Whether the code and data are stored in little-endian byte order.
+    little_endian: bool,
+    /// The value of the global pointer, which is used to address the GOT and the small data.
+    global_pointer: Option<usize>,
     /// The preferred image base.
     image_base: usize,
     /// Maps virtual addresses to file offsets.
     rva_mapping: RangeMap<usize, usize>,
     /// Maps file offsets to virtual addresses.
     file_mapping: RangeMap<usize, usize>,
+    /// Maps virtual addresses to the names of the modules loaded there.
+    modules: RangeMap<usize, String>,
+    /// The file ranges that hold code.
+    code_ranges: RangeSet<usize>,
+    /// Maps the virtual addresses of the slots that hold an absolute address to their sizes.
+    relocations: BTreeMap<usize, usize>,
 }
 
 impl ExecutableMapping {
+    /// Creates an empty mapping. This is useful for raw images, such as firmware images, that lack
+    /// any headers describing where they get loaded. The load address ranges can then be added
+    /// using `insert`.
+    pub fn new() -> Self {
+        Self {
+            architecture: Architecture::Unknown,
+            is_pe: false,
+            little_endian: true,
+            global_pointer: None,
+            image_base: 0,
+            rva_mapping: RangeMap::new(),
+            file_mapping: RangeMap::new(),
+            modules: RangeMap::new(),
+            code_ranges: RangeSet::new(),
+            relocations: BTreeMap::new(),
+        }
+    }
+
     pub fn parse(bytes: &[u8]) -> Result<Self> {
-        let mut image_base = 0;
-        let mut rva_mapping = RangeMap::new();
-        let mut file_mapping = RangeMap::new();
+        let mut mapping = Self::new();
+
+        // WebAssembly modules have their data segments copied into linear memory, which means the
+        // resources are addressed using linear memory addresses instead.
+        if wasm::is_wasm(bytes) {
+            mapping.architecture = Architecture::Wasm;
+
+            for (address, file_range) in wasm::data_segments(bytes) {
+                // Empty segments don't map anything.
+                if file_range.is_empty() {
+                    continue;
+                }
+
+                let rva_range = address..address + file_range.len();
+
+                // Track the mappings.
+                mapping.insert(file_range, rva_range);
+            }
+
+            return Ok(mapping);
+        }
 
         match Object::parse(&bytes)? {
             Object::Elf(elf) => {
-                for segment in elf.program_headers {
+                // Core dumps only contain the memory that was actually dumped, so we should only map
+                // the part of each segment that is present in the file.
+                let is_core = elf.header.e_type == ET_CORE;
+
+                mapping.architecture = match elf.header.e_machine {
+                    EM_386 => Architecture::X86,
+                    EM_X86_64 => Architecture::X86_64,
+                    EM_ARM => Architecture::Arm,
+                    EM_AARCH64 => Architecture::AArch64,
+                    EM_RISCV => Architecture::RiscV,
+                    EM_MIPS => Architecture::Mips,
+                    EM_PPC | EM_PPC64 => Architecture::PowerPC,
+                    _ => Architecture::Unknown,
+                };
+
+                mapping.little_endian = elf.little_endian;
+
+                // The global pointer is set up at runtime, but is usually defined as a symbol, or
+                // at a fixed bias from the start of the GOT.
+                let (symbol_name, got_bias) = match mapping.architecture {
+                    Architecture::RiscV => ("__global_pointer$", None),
+                    Architecture::Mips => ("_gp", Some(0x7ff0)),
+                    Architecture::PowerPC if elf.is_64 => (".TOC.", Some(0x8000)),
+                    _ => ("", None),
+                };
+
+                if !symbol_name.is_empty() {
+                    let symbol = elf.syms.iter().find(|sym| {
+                        sym.st_shndx != 0 && elf.strtab.get_at(sym.st_name) == Some(symbol_name)
+                    });
+
+                    let got = elf.section_headers.iter().find(|section| {
+                        elf.shdr_strtab.get_at(section.sh_name) == Some(".got")
+                    });
+
+                    mapping.global_pointer = match (symbol, got, got_bias) {
+                        (Some(sym), _, _) => Some(sym.st_value as usize),
+                        (_, Some(got), Some(got_bias)) => Some(got.sh_addr as usize + got_bias),
+                        _ => None,
+                    };
+                }
+
+                for segment in &elf.program_headers {
                     if segment.p_type != PT_LOAD || segment.p_filesz == 0 || segment.p_memsz == 0 {
                         continue;
                     }
 
                     // Calculate the file range of this section.
-                    let start = segment.p_offset as usize;
-                    let end = start + segment.p_filesz as usize;
-                    let file_range = start..end;
+                    let file_range = clip_file_range(bytes, segment.p_offset, segment.p_filesz);
 
                     // Calculate the virtual address range of this section.
+                    let size = if is_core { segment.p_filesz } else { segment.p_memsz };
                     let start = segment.p_vaddr as usize;
-                    let end = start + segment.p_memsz as usize;
+                    let end = start.saturating_add(size as usize);
                     let rva_range = start..end;
 
                     // Track the mappings.
-                    file_mapping.insert(file_range.clone(), rva_range.start);
-                    rva_mapping.insert(rva_range, file_range.start);
+                    mapping.insert(file_range.clone(), rva_range);
+
+                    if segment.p_flags & PF_X != 0 {
+                        mapping.insert_code(file_range);
+                    }
+                }
+
+                // Core dumps list the files that were mapped into the process in the NT_FILE note,
+                // which tells us what module each address belongs to.
+                if is_core {
+                    for note in elf.iter_note_headers(bytes).into_iter().flatten() {
+                        let note = match note {
+                            Ok(note) => note,
+                            _ => break,
+                        };
+
+                        if note.n_type != NT_FILE {
+                            continue;
+                        }
+
+                        for (range, path) in parse_file_note(note.desc, elf.is_64, elf.little_endian) {
+                            mapping.insert_module(range, path);
+                        }
+                    }
                 }
             }
             Object::PE(pe) => {
-                image_base = pe.image_base as usize;
+                mapping.image_base = pe.image_base as usize;
+                mapping.is_pe = true;
+
+                mapping.architecture = match pe.header.coff_header.machine {
+                    COFF_MACHINE_X86 => Architecture::X86,
+                    COFF_MACHINE_X86_64 => Architecture::X86_64,
+                    COFF_MACHINE_ARMNT => Architecture::Arm,
+                    COFF_MACHINE_ARM64 => Architecture::AArch64,
+                    _ => Architecture::Unknown,
+                };
 
-                for section in pe.sections {
+                for section in &pe.sections {
                     // Calculate the file range of this section.
-                    let start = section.pointer_to_raw_data as usize;
-                    let end = start + section.size_of_raw_data as usize;
-                    let file_range = start..end;
+                    let file_range = clip_file_range(bytes, section.pointer_to_raw_data as u64, section.size_of_raw_data as u64);
 
                     // Calculate the virtual address range of this section.
                     let start = section.virtual_address as usize;
@@ -58,23 +350,155 @@ impl ExecutableMapping {
                     let rva_range = start..end;
 
                     // Track the mappings.
-                    file_mapping.insert(file_range.clone(), rva_range.start);
-                    rva_mapping.insert(rva_range, file_range.start);
+                    mapping.insert(file_range.clone(), rva_range);
+
+                    if section.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0 {
+                        mapping.insert_code(file_range);
+                    }
+                }
+
+                // The base relocation table lists the slots that hold an absolute address, which
+                // the loader fixes up if the image cannot be loaded at its preferred base.
+                let table = pe.header.optional_header.and_then(|header| *header.data_directories.get_base_relocation_table());
+
+                if let Some(table) = table {
+                    let start = mapping.rva_to_file_offset(mapping.image_base + table.virtual_address as usize);
+                    let table = start.and_then(|start| bytes.get(start..)?.get(..table.size as usize));
+
+                    for (rva, size) in parse_base_relocations(table.unwrap_or_default()) {
+                        mapping.insert_relocation(mapping.image_base + rva, size);
+                    }
+                }
+            }
+            Object::Mach(Mach::Binary(macho)) => {
+                mapping.architecture = match macho.header.cputype {
+                    CPU_TYPE_X86 => Architecture::X86,
+                    CPU_TYPE_X86_64 => Architecture::X86_64,
+                    CPU_TYPE_ARM => Architecture::Arm,
+                    CPU_TYPE_ARM64 => Architecture::AArch64,
+                    _ => Architecture::Unknown,
+                };
+
+                for segment in &macho.segments {
+                    if segment.filesize == 0 || segment.vmsize == 0 {
+                        continue;
+                    }
+
+                    // Calculate the file range of this segment.
+                    let file_range = clip_file_range(bytes, segment.fileoff, segment.filesize);
+
+                    // Calculate the virtual address range of this segment.
+                    let start = segment.vmaddr as usize;
+                    let end = start.saturating_add(segment.vmsize as usize);
+                    let rva_range = start..end;
+
+                    // Track the mappings.
+                    mapping.insert(file_range.clone(), rva_range);
+
+                    if segment.initprot & VM_PROT_EXECUTE != 0 {
+                        mapping.insert_code(file_range);
+                    }
                 }
             }
             _ => (),
         }
 
-        Ok(Self {
-            image_base,
-            rva_mapping,
-            file_mapping,
-        })
+        Ok(mapping)
+    }
+
+    /// Maps the file range `file_range` to the virtual address range `rva_range`. Only the part
+    /// that both ranges have in common is mapped, as the virtual address range may extend past the
+    /// bytes stored in the file, e.g. to hold zero-initialized data.
+    pub fn insert(&mut self, file_range: Range<usize>, rva_range: Range<usize>) {
+        let size = file_range.len().min(rva_range.len());
+
+        if size == 0 {
+            return;
+        }
+
+        self.file_mapping.insert(file_range.start..file_range.start + size, rva_range.start);
+        self.rva_mapping.insert(rva_range.start..rva_range.start + size, file_range.start);
+    }
+
+    /// Tracks that the file range `file_range` holds code.
+    pub fn insert_code(&mut self, file_range: Range<usize>) {
+        if file_range.is_empty() {
+            return;
+        }
+
+        self.code_ranges.insert(file_range);
+    }
+
+    /// Tracks that the slot at the virtual address `address` holds an absolute address of `size`
+    /// bytes.
+    pub fn insert_relocation(&mut self, address: usize, size: usize) {
+        self.relocations.insert(address, size);
+    }
+
+    /// Checks whether we know which slots hold an absolute address.
+    pub fn has_relocations(&self) -> bool {
+        !self.relocations.is_empty()
+    }
+
+    /// Checks whether the slot at the virtual address `address` holds an absolute address.
+    pub fn is_relocated(&self, address: usize) -> bool {
+        self.relocations.contains_key(&address)
+    }
+
+    /// Yields the virtual address and the size of every slot that holds an absolute address.
+    pub fn relocations(&self) -> Vec<(usize, usize)> {
+        self.relocations.iter().map(|(address, size)| (*address, *size)).collect()
+    }
+
+    /// Yields the instruction set architecture.
+    pub fn architecture(&self) -> Architecture {
+        self.architecture
+    }
+
+    /// Checks whether the image is a PE image.
+    pub fn is_pe(&self) -> bool {
+        self.is_pe
+    }
+
+    /// Checks whether the code and data are stored in little-endian byte order.
+    pub fn is_little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    /// Yields the value of the global pointer, if known.
+    pub fn global_pointer(&self) -> Option<usize> {
+        self.global_pointer
+    }
+
+    /// Yields the file ranges that hold code. If we don't know which file ranges hold code, e.g.
+    /// for raw images, all mapped file ranges are yielded instead.
+    pub fn code_ranges(&self) -> Vec<Range<usize>> {
+        if self.code_ranges.is_empty() {
+            return self.file_mapping.iter().map(|(file_range, _)| file_range.clone()).collect();
+        }
+
+        self.code_ranges.iter().cloned().collect()
+    }
+
+    /// Tracks that the module `name` is loaded at the virtual address range `rva_range`.
+    pub fn insert_module(&mut self, rva_range: Range<usize>, name: String) {
+        if rva_range.is_empty() {
+            return;
+        }
+
+        self.modules.insert(rva_range, name);
+    }
+
+    /// Looks up the name of the module that the file offset belongs to, if any.
+    pub fn module_at(&self, file_offset: usize) -> Option<&str> {
+        let rva = self.file_offset_to_rva(file_offset)?;
+
+        self.modules.get(&rva).map(|name| name.as_str())
     }
 
     /// Calculates the file offset from the virtual address.
     pub fn rva_to_file_offset(&self, rva: usize) -> Option<usize> {
-        let rva = rva - self.image_base;
+        let rva = rva.checked_sub(self.image_base)?;
 
         let (rva_range, file_base) = match self.rva_mapping.get_key_value(&rva) {
             Some(segment) => segment,
@@ -94,3 +518,160 @@ impl ExecutableMapping {
         Some(file_offset + rva_base + self.image_base - file_range.start)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes an `NT_FILE` note with the given word size and byte order.
+    fn file_note(mappings: &[(u64, u64, &str)], is_64: bool, little_endian: bool) -> Vec<u8> {
+        let mut desc = vec![];
+
+        let mut push = |value: u64| {
+            let bytes = if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+
+            if !is_64 {
+                desc.extend_from_slice(if little_endian { &bytes[..4] } else { &bytes[4..] });
+            } else {
+                desc.extend_from_slice(&bytes);
+            }
+        };
+
+        push(mappings.len() as u64);
+        push(0x1000);
+
+        for (start, end, _) in mappings {
+            push(*start);
+            push(*end);
+            push(0);
+        }
+
+        for (_, _, path) in mappings {
+            desc.extend_from_slice(path.as_bytes());
+            desc.push(0);
+        }
+
+        desc
+    }
+
+    #[test]
+    fn parses_file_notes() {
+        let mappings = [(0x400000, 0x401000, "/usr/bin/app"), (0x7f0000, 0x7f8000, "/usr/lib/libQt6Core.so.6")];
+
+        for (is_64, little_endian) in [(true, true), (false, true), (false, false)] {
+            let desc = file_note(&mappings, is_64, little_endian);
+
+            assert_eq!(
+                parse_file_note(&desc, is_64, little_endian),
+                vec![
+                    (0x400000..0x401000, "/usr/bin/app".to_string()),
+                    (0x7f0000..0x7f8000, "/usr/lib/libQt6Core.so.6".to_string()),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_file_notes() {
+        let mut desc = file_note(&[(0x400000, 0x401000, "/usr/bin/app")], true, true);
+
+        // Claim more mappings than the note holds.
+        desc[0] = 0xff;
+
+        assert!(parse_file_note(&desc, true, true).is_empty());
+    }
+
+    /// Encodes a 64-bit little endian ELF core dump with a single executable segment of `size`
+    /// bytes at 0x400000, whose contents start right after the program header.
+    fn core_dump(size: u64) -> Vec<u8> {
+        let mut bytes = b"\x7fELF\x02\x01\x01".to_vec();
+        bytes.resize(16, 0);
+
+        // The file header, with the program header table at 0x40.
+        bytes.extend_from_slice(&ET_CORE.to_le_bytes());
+        bytes.extend_from_slice(&EM_X86_64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&[0u64, 0x40, 0].map(u64::to_le_bytes).concat());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[0x40u16, 0x38, 1, 0x40, 0, 0].map(u16::to_le_bytes).concat());
+
+        // The program header of the segment.
+        bytes.extend_from_slice(&[PT_LOAD, PF_X].map(u32::to_le_bytes).concat());
+        bytes.extend_from_slice(&[0x78u64, 0x400000, 0, size, size, 0x1000].map(u64::to_le_bytes).concat());
+
+        bytes
+    }
+
+    #[test]
+    fn clips_segments_to_the_file() {
+        // The segment claims to hold 0x1000 bytes, but the file was truncated after 0x88 bytes.
+        let mut bytes = core_dump(0x1000);
+        bytes.resize(0x100, 0);
+
+        let mapping = ExecutableMapping::parse(&bytes).unwrap();
+
+        assert_eq!(mapping.rva_to_file_offset(0x400000), Some(0x78));
+        assert_eq!(mapping.rva_to_file_offset(0x400087), Some(0xff));
+        assert_eq!(mapping.rva_to_file_offset(0x400088), None);
+        assert_eq!(mapping.file_offset_to_rva(0xff), Some(0x400087));
+        assert_eq!(mapping.code_ranges(), vec![0x78..0x100]);
+
+        // The segment lies past the end of the file.
+        bytes.truncate(0x78);
+
+        let mapping = ExecutableMapping::parse(&bytes).unwrap();
+
+        assert_eq!(mapping.rva_to_file_offset(0x400000), None);
+    }
+
+    #[test]
+    fn maps_the_common_part_of_both_ranges() {
+        let mut mapping = ExecutableMapping::new();
+
+        // The virtual address range holds zero-initialized data past the end of the file range.
+        mapping.insert(0x100..0x200, 0x1000..0x2000);
+
+        assert_eq!(mapping.rva_to_file_offset(0x10ff), Some(0x1ff));
+        assert_eq!(mapping.rva_to_file_offset(0x1100), None);
+        assert_eq!(mapping.file_offset_to_rva(0x1ff), Some(0x10ff));
+    }
+
+    /// Encodes a block of the base relocation table for the given page.
+    fn relocation_block(page: u32, entries: &[u16]) -> Vec<u8> {
+        let mut block = page.to_le_bytes().to_vec();
+        block.extend_from_slice(&(8 + 2 * entries.len() as u32).to_le_bytes());
+
+        for entry in entries {
+            block.extend_from_slice(&entry.to_le_bytes());
+        }
+
+        block
+    }
+
+    #[test]
+    fn parses_base_relocations() {
+        // The first block is padded with an IMAGE_REL_BASED_ABSOLUTE entry, which doesn't relocate
+        // anything.
+        let mut table = relocation_block(0x1000, &[0x3004, 0xa010, 0x3ffc, 0x0000]);
+        table.extend_from_slice(&relocation_block(0x3000, &[0x3008]));
+
+        assert_eq!(parse_base_relocations(&table), vec![(0x1004, 4), (0x1010, 8), (0x1ffc, 4), (0x3008, 4)]);
+    }
+
+    #[test]
+    fn rejects_truncated_base_relocations() {
+        // The last block claims to be larger than the table.
+        let mut table = relocation_block(0x1000, &[0x3004]);
+        table.extend_from_slice(&relocation_block(0x2000, &[0x3008, 0x300c]));
+        table.truncate(table.len() - 2);
+
+        assert_eq!(parse_base_relocations(&table), vec![(0x1004, 4), (0x2008, 4)]);
+
+        // A block that is too small to hold its own header ends the table.
+        let mut table = relocation_block(0x1000, &[]);
+        table[4] = 4;
+        table.extend_from_slice(&relocation_block(0x2000, &[0x3008]));
+
+        assert!(parse_base_relocations(&table).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+/// The language whose rules are used to decode the escape sequences of a literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    /// C++ string literals, where hexadecimal escape sequences consume as many digits as they can
+    /// and unknown escape sequences yield the escaped character.
+    Cpp,
+    /// Python bytes literals, where hexadecimal escape sequences consist of exactly two digits,
+    /// a backslash at the end of a line continues the literal and unknown escape sequences are
+    /// kept as is.
+    Python,
+}
+
+/// Decodes the escape sequence starting at the given offset, which should point just past the
+/// backslash, and appends the resulting bytes to `bytes`.
+///
+/// Yields the offset past the escape sequence, or `None` if it is malformed.
+pub fn decode_escape(source: &[u8], mut offset: usize, dialect: Dialect, bytes: &mut Vec<u8>) -> Option<usize> {
+    let c = *source.get(offset)?;
+    offset += 1;
+
+    let value = match c {
+        b'\n' if dialect == Dialect::Python => return Some(offset),
+        b'\r' if dialect == Dialect::Python => {
+            if source.get(offset) == Some(&b'\n') {
+                offset += 1;
+            }
+
+            return Some(offset);
+        }
+        b'x' => {
+            let end = match dialect {
+                Dialect::Cpp => source.len(),
+                Dialect::Python => offset.checked_add(2).filter(|end| *end <= source.len())?,
+            };
+
+            let mut value = 0u32;
+            let start = offset;
+
+            while offset < end && source[offset].is_ascii_hexdigit() {
+                value = (value << 4) | (source[offset] as char).to_digit(16)?;
+                offset += 1;
+            }
+
+            if offset == start || (dialect == Dialect::Python && offset != end) {
+                return None;
+            }
+
+            value as u8
+        }
+        b'0'..=b'7' => {
+            // Octal escape sequences consume up to three digits.
+            let mut value = (c - b'0') as u32;
+            let end = offset + 2;
+
+            while offset < end && offset < source.len() && (b'0'..=b'7').contains(&source[offset]) {
+                value = (value << 3) | (source[offset] - b'0') as u32;
+                offset += 1;
+            }
+
+            value as u8
+        }
+        b'n' => b'\n',
+        b't' => b'\t',
+        b'r' => b'\r',
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'f' => 0x0c,
+        b'v' => 0x0b,
+        b'\\' | b'\'' | b'"' => c,
+        c => {
+            if dialect == Dialect::Python {
+                bytes.push(b'\\');
+            }
+
+            c
+        }
+    };
+
+    bytes.push(value);
+
+    Some(offset)
+}
+
+/// Decodes an integer literal, which is either hexadecimal (`0x1f`), octal (`017`) or decimal.
+pub fn parse_integer(literal: &str) -> Option<u32> {
+    if let Some(hex) = literal.strip_prefix("0x").or_else(|| literal.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if literal.len() > 1 && literal.starts_with('0') {
+        u32::from_str_radix(&literal[1..], 8).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes the escape sequence in `source`, which excludes the backslash.
+    fn decode(source: &[u8], dialect: Dialect) -> Option<(Vec<u8>, usize)> {
+        let mut bytes = vec![];
+        let offset = decode_escape(source, 0, dialect, &mut bytes)?;
+
+        Some((bytes, offset))
+    }
+
+    #[test]
+    fn decodes_hexadecimal_escapes() {
+        assert_eq!(decode(b"x1f", Dialect::Cpp), Some((vec![0x1f], 3)));
+        assert_eq!(decode(b"x0001f\"", Dialect::Cpp), Some((vec![0x1f], 6)));
+        assert_eq!(decode(b"x1fab", Dialect::Python), Some((vec![0x1f], 3)));
+        assert_eq!(decode(b"xg", Dialect::Cpp), None);
+        assert_eq!(decode(b"x1", Dialect::Python), None);
+        assert_eq!(decode(b"x1g", Dialect::Python), None);
+    }
+
+    #[test]
+    fn decodes_octal_escapes() {
+        assert_eq!(decode(b"0", Dialect::Cpp), Some((vec![0], 1)));
+        assert_eq!(decode(b"1234", Dialect::Python), Some((vec![0o123], 3)));
+        assert_eq!(decode(b"08", Dialect::Cpp), Some((vec![0], 1)));
+    }
+
+    #[test]
+    fn decodes_simple_escapes() {
+        assert_eq!(decode(b"n", Dialect::Cpp), Some((vec![b'\n'], 1)));
+        assert_eq!(decode(b"\"", Dialect::Python), Some((vec![b'"'], 1)));
+        assert_eq!(decode(b"q", Dialect::Cpp), Some((vec![b'q'], 1)));
+        assert_eq!(decode(b"q", Dialect::Python), Some((vec![b'\\', b'q'], 1)));
+        assert_eq!(decode(b"", Dialect::Cpp), None);
+    }
+
+    #[test]
+    fn decodes_line_continuations() {
+        assert_eq!(decode(b"\n", Dialect::Python), Some((vec![], 1)));
+        assert_eq!(decode(b"\r\n", Dialect::Python), Some((vec![], 2)));
+        assert_eq!(decode(b"\n", Dialect::Cpp), Some((vec![b'\n'], 1)));
+    }
+
+    #[test]
+    fn parses_integer_literals() {
+        assert_eq!(parse_integer("0x1f"), Some(0x1f));
+        assert_eq!(parse_integer("0XFF"), Some(0xff));
+        assert_eq!(parse_integer("017"), Some(0o17));
+        assert_eq!(parse_integer("0"), Some(0));
+        assert_eq!(parse_integer("42"), Some(42));
+        assert_eq!(parse_integer("0x"), None);
+        assert_eq!(parse_integer("09"), None);
+        assert_eq!(parse_integer("a"), None);
+    }
+}
@@ -1,27 +1,76 @@
+mod apk;
 mod blob;
+mod callsite;
+mod cpp;
 mod executable;
+mod literal;
+mod minidump;
 mod name;
+mod object;
+mod process;
+mod python;
+mod qres;
+mod resource;
+mod symbols;
 mod tree;
+mod wasm;
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use goblin::Object;
 use goblin::elf::program_header::PT_LOAD;
-use rangemap::RangeMap;
+use rangemap::{RangeMap, RangeSet};
 use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::executable::ExecutableMapping;
 use crate::name::scan_names;
+use crate::symbols::ResourceSymbols;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    input: String,
+    #[clap(required_unless_present = "pid")]
+    input: Option<String>,
+
+    /// Reads the memory of the running process with the given PID instead of a file.
+    #[clap(long, conflicts_with = "input")]
+    pid: Option<u32>,
 
     #[clap(short, long)]
     output: Option<String>,
+
+    /// Treats the input as a raw image (e.g. a firmware image) that gets loaded at the given
+    /// address. Optionally, the file offset and the size of the loaded range can be specified. Can
+    /// be used multiple times.
+    #[clap(long, conflicts_with = "pid", value_name = "ADDRESS[:OFFSET[:SIZE]]", parse(try_from_str = parse_load_range))]
+    load: Vec<(usize, usize, Option<usize>)>,
+}
+
+/// Parses a number that is either decimal or hexadecimal when prefixed with `0x`.
+fn parse_number(s: &str) -> Result<usize> {
+    let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(s) => usize::from_str_radix(s, 16)?,
+        _ => s.parse()?,
+    };
+
+    Ok(value)
+}
+
+/// Parses a load address range of the form `ADDRESS[:OFFSET[:SIZE]]`.
+fn parse_load_range(s: &str) -> Result<(usize, usize, Option<usize>)> {
+    let mut parts = s.split(':');
+
+    let address = parse_number(parts.next().unwrap_or_default())?;
+    let offset = parts.next().map(parse_number).transpose()?.unwrap_or(0);
+    let size = parts.next().map(parse_number).transpose()?;
+
+    if parts.next().is_some() {
+        anyhow::bail!("expected ADDRESS[:OFFSET[:SIZE]]");
+    }
+
+    Ok((address, offset, size))
 }
 
 /// Calculates the distance between two ranges.
@@ -35,36 +84,197 @@ fn distance(lhs: &Range<usize>, rhs: &Range<usize>) -> usize {
     }
 }
 
-fn main() -> Result<()> {
-    // Parse the arguments.
-    let args = Args::parse();
+/// Looks up the initializer in `initializers` that references the tree at the file offset
+/// `tree_offset`.
+///
+/// Yields the name of the qrc file of the initializer.
+fn find_initializer<'a>(
+    initializers: &'a [(String, Range<usize>)],
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    tree_offset: usize,
+) -> Option<&'a str> {
+    for (qrc_name, range) in initializers {
+        if blob::references(bytes, mapping, range.clone(), tree_offset) {
+            println!("File tree belongs to qInitResources_{}...", qrc_name);
 
-    let output = args.output
-        .map(|output| PathBuf::from(output))
-        .unwrap_or(PathBuf::new());
+            return Some(qrc_name);
+        }
+    }
 
-    let bytes = std::fs::read(&args.input)
-        .with_context(|| format!("could not open file '{}'.", &args.input))?;
+    None
+}
 
-    let mapping = ExecutableMapping::parse(&bytes)?;
+/// Extracts the Qt resources from the relocatable object in the given byte array `bytes` to the
+/// directory `output`. If the object contains more than one tree, each tree is extracted to its own
+/// subdirectory.
+fn extract_object(output: &Path, bytes: &[u8]) -> Result<()> {
+    let groups = object::resource_symbols(bytes)?;
+
+    for (index, symbols) in groups.iter().enumerate() {
+        let root = if groups.len() > 1 {
+            output.join(index.to_string())
+        } else {
+            output.to_path_buf()
+        };
+
+        symbols::extract_symbols(root, bytes, symbols)?;
+    }
+
+    Ok(())
+}
 
-    let names = scan_names(&bytes);
+/// Extracts the Qt resources from the arrays `bytes` decoded from the source code generated by rcc
+/// to the directory `output`. As the arrays are all there is, the blobs can't be located without
+/// the data array, in which case the tree is skipped.
+fn extract_source(output: &Path, bytes: &[u8], symbols: &ResourceSymbols) -> Result<()> {
+    if symbols.data.is_none() {
+        println!("Found tree without data array, skipping file tree...");
+
+        return Ok(());
+    }
+
+    symbols::extract_symbols(output, bytes, symbols)?;
+
+    Ok(())
+}
+
+/// Locates and extracts the Qt resources in the given byte array `bytes` to the directory
+/// `output`. The mapping `mapping` is used to translate virtual addresses into file offsets.
+fn extract(output: &Path, bytes: &[u8], mapping: &ExecutableMapping) -> Result<()> {
+    // Binary resource files produced by `rcc -binary` tell us exactly where everything is.
+    if let Some(header) = qres::parse_header(bytes) {
+        println!("Found binary resource file (version {})...", header.version);
+        println!("Extracting file tree...");
+
+        return qres::extract_qres(output, bytes, &header);
+    }
+
+    // Windows applications may store binary resource images as resources in their resource
+    // directory. Analyse each resource on its own and extract it to a separate directory. A
+    // resource directory that can't be parsed shouldn't keep us from applying the heuristics.
+    let mut carved = RangeSet::new();
+
+    let resources = if mapping.is_pe() {
+        resource::rcdata_resources(bytes, mapping).unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    for resource in resources {
+        let range = resource.file_range.clone();
+
+        println!("Found resource {}/{}/{} at 0x{:x}-0x{:x}...", resource.kind, resource.name, resource.language, range.start, range.end);
+
+        if range.is_empty() {
+            continue;
+        }
+
+        let root = output
+            .join("rsrc")
+            .join(&resource.kind)
+            .join(&resource.name)
+            .join(&resource.language);
+
+        extract(&root, &bytes[range.clone()], &ExecutableMapping::new())?;
+        carved.insert(range);
+    }
+
+    // Carve out any binary resource images that are embedded in the file. Each image is extracted
+    // to its own directory.
+    for (offset, (image_range, header)) in qres::find_images(bytes) {
+        // Skip the images that are part of a resource we already extracted.
+        if carved.contains(&offset) {
+            continue;
+        }
+
+        println!("Found binary resource image at 0x{:x}-0x{:x} (version {})...", image_range.start, image_range.end, header.version);
+
+        if let Some(module) = mapping.module_at(offset) {
+            println!("Binary resource image belongs to module {}...", module);
+        }
+
+        println!("Extracting file tree...");
+
+        // A false positive shouldn't keep us from extracting the other images.
+        if let Err(error) = qres::extract_qres(output.join(format!("qres_{:x}", offset)), &bytes[offset..], &header) {
+            println!("Failed to extract binary resource image at 0x{:x}: {}", offset, error);
+            continue;
+        }
+
+        carved.insert(image_range);
+    }
+
+    // Unstripped images still have the symbols of the arrays emitted by rcc, which tell us exactly
+    // where everything is. The byte array is not necessarily an image though, e.g. when dealing
+    // with process memory, so ignore it if it can't be parsed.
+    let mut groups = object::image_symbols(bytes, mapping).unwrap_or_default();
+    let initializers = object::initializers(bytes, mapping).unwrap_or_default();
+
+    // The calls to qRegisterResourceData tell us exactly where everything is as well, even if the
+    // image has been stripped.
+    for (offset, symbols) in callsite::find_registrations(bytes, mapping).unwrap_or_default() {
+        if groups.iter().any(|group| group.tree == symbols.tree) {
+            continue;
+        }
+
+        println!("Found call to qRegisterResourceData at 0x{:x}...", offset);
+
+        groups.push(symbols);
+    }
+
+    for (index, symbols) in groups.iter().enumerate() {
+        let qrc_name = symbols.tree
+            .and_then(|tree_offset| find_initializer(&initializers, bytes, mapping, tree_offset));
+
+        let root = if let Some(qrc_name) = qrc_name {
+            output.join(qrc_name)
+        } else if groups.len() > 1 {
+            output.join(index.to_string())
+        } else {
+            output.to_path_buf()
+        };
+
+        // Skip the names of the trees we extracted when falling back to the heuristics.
+        if symbols::extract_symbols(root, bytes, symbols)? {
+            if let Some(names) = symbols.names {
+                carved.insert(names..names + 1);
+            }
+        }
+    }
+
+    let names = scan_names(bytes);
 
     for (_, (name_range, names)) in names.iter() {
+        // Skip the names that are part of a binary resource image or a tree we already extracted.
+        if carved.contains(&name_range.start) {
+            continue;
+        }
+
         println!("Found set of names at 0x{:x}-0x{:x}...", name_range.start, name_range.end);
 
-        let trees = tree::find_trees(names, &bytes);
+        let trees = tree::find_trees(names, bytes);
 
         // Score the trees by their proximity to this name range.
-        let trees: BTreeMap<usize, Range<usize>> = trees
+        let trees: BTreeMap<usize, (Range<usize>, u32)> = trees
             .into_iter()
-            .map(|(_, tree_range)| (distance(name_range, &tree_range), tree_range))
+            .map(|(_, (tree_range, version))| (distance(name_range, &tree_range), (tree_range, version)))
             .collect();
 
-        'outer: for (score, tree_range) in trees {
-            println!("Found file tree at 0x{:x}-0x{:x} with proximity score {}...", tree_range.start, tree_range.end, score);
+        'outer: for (score, (tree_range, version)) in trees {
+            println!("Found file tree at 0x{:x}-0x{:x} (version {}) with proximity score {}...", tree_range.start, tree_range.end, version, score);
 
-            let mut blobs = tree::find_blobs(tree_range.start, &bytes);
+            if let Some(module) = mapping.module_at(tree_range.start) {
+                println!("File tree belongs to module {}...", module);
+            }
+
+            // Extract the tree to a directory named after its qrc file, if we know it.
+            let root = match find_initializer(&initializers, bytes, mapping, tree_range.start) {
+                Some(qrc_name) => output.join(qrc_name),
+                _ => output.to_path_buf(),
+            };
+
+            let mut blobs = tree::find_blobs(tree_range.start, bytes, version);
 
             /*if blobs.is_empty() {
                 // Align the offset to 8 bytes.
@@ -88,30 +298,15 @@ fn main() -> Result<()> {
             }*/
 
             // FIXME: calculate the actual blob range?
-            if blobs.is_empty() {
-                let scores = blob::find_blobs_push(&bytes, &mapping, tree_range.start, name_range.start);
-
-                if let Some((score, blob_offset)) = scores.into_iter().next() {
-                    println!("Found PUSH instruction with blob offset 0x{:x} and proximity score {}", blob_offset, score);
-                    blobs.insert(blob_offset, blob_offset..blob_offset + 1);
-                }
-            }
-
-            if blobs.is_empty() {
-                let scores = blob::find_blobs_lea(&bytes, &mapping, tree_range.start, name_range.start, false);
-
-                if let Some((score, blob_offset)) = scores.into_iter().next() {
-                    println!("Found LEA instruction with blob offset 0x{:x} and proximity score {}", blob_offset, score);
-                    blobs.insert(blob_offset, blob_offset..blob_offset + 1);
+            for (description, locator) in blob::locators(mapping.architecture()) {
+                if !blobs.is_empty() {
+                    break;
                 }
-            }
 
-            // FIXME: check if we are dealing with PE or ELF.
-            if blobs.is_empty() {
-                let scores = blob::find_blobs_lea(&bytes, &mapping, tree_range.start, name_range.start, true);
+                let scores = locator(bytes, mapping, tree_range.start, name_range.start);
 
                 if let Some((score, blob_offset)) = scores.into_iter().next() {
-                    println!("Found LEA instruction with blob offset 0x{:x} and proximity score {}", blob_offset, score);
+                    println!("Found {} with blob offset 0x{:x} and proximity score {}", description, blob_offset, score);
                     blobs.insert(blob_offset, blob_offset..blob_offset + 1);
                 }
             }
@@ -126,7 +321,12 @@ fn main() -> Result<()> {
                 println!("Found data blobs at 0x{:x}-0{:x} with proximity score {}...", blob_range.start, blob_range.end, score);
                 println!("Extracting file tree...");
 
-                if let Ok(()) = tree::extract_tree(&output, names, &bytes[blob_range.start..], &bytes[tree_range.start..], 0, 1) {
+                let blobs = match bytes.get(blob_range.start..) {
+                    Some(blobs) => blobs,
+                    _ => continue,
+                };
+
+                if let Ok(()) = tree::extract_tree(&root, names, blobs, &bytes[tree_range.start..], 0, 1, version) {
                     break 'outer;
                 }
             }
@@ -135,3 +335,144 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn main() -> Result<()> {
+    // Parse the arguments.
+    let args = Args::parse();
+
+    let output = args.output
+        .map(|output| PathBuf::from(output))
+        .unwrap_or(PathBuf::new());
+
+    // Running processes may have loaded more resources than the executable on disk contains,
+    // e.g. through plugins or binary resource files that were registered at runtime.
+    if let Some(pid) = args.pid {
+        let (bytes, mapping) = process::read_process(pid)?;
+
+        return extract(&output, &bytes, &mapping);
+    }
+
+    let input = args.input
+        .context("no input file was given.")?;
+
+    let bytes = std::fs::read(&input)
+        .with_context(|| format!("could not open file '{}'.", &input))?;
+
+    // Raw images lack any headers, so rely on the load address ranges that were given instead.
+    if !args.load.is_empty() {
+        let mut mapping = ExecutableMapping::new();
+
+        for (address, offset, size) in args.load {
+            // Make sure that the range is not empty and lies within the file.
+            let end = match size {
+                Some(size) => offset.checked_add(size),
+                _ => Some(bytes.len()),
+            };
+
+            let file_range = match end {
+                Some(end) if offset < end && end <= bytes.len() => offset..end,
+                _ => anyhow::bail!("load range at offset 0x{:x} does not lie within the file of 0x{:x} bytes.", offset, bytes.len()),
+            };
+
+            let rva_range = match address.checked_add(file_range.len()) {
+                Some(end) => address..end,
+                _ => anyhow::bail!("load range at address 0x{:x} does not fit in the address space.", address),
+            };
+
+            println!("Mapping 0x{:x}-0x{:x} to 0x{:x}-0x{:x}...", file_range.start, file_range.end, rva_range.start, rva_range.end);
+
+            mapping.insert(file_range, rva_range);
+        }
+
+        return extract(&output, &bytes, &mapping);
+    }
+
+    // Static archives consist of relocatable objects, which have not been linked yet. Instead of
+    // relying on the heuristics, we look up the arrays emitted by rcc in the symbol table.
+    if object::is_archive(&bytes) {
+        for (name, member) in object::archive_members(&bytes)? {
+            println!("Found archive member {}...", name);
+
+            // Archives may contain members other than objects, such as import descriptors, that
+            // can't be parsed.
+            if let Err(error) = extract_object(&output.join(&name), member) {
+                println!("Failed to parse archive member {}: {}", name, error);
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Anything that fails to parse as an object is left to the other heuristics.
+    if !object::resource_symbols(&bytes).unwrap_or_default().is_empty() {
+        return extract_object(&output, &bytes);
+    }
+
+    // C++ sources generated by rcc contain the arrays as literals.
+    if let Some((bytes, symbols)) = cpp::parse_source(&bytes) {
+        return extract_source(&output, &bytes, &symbols);
+    }
+
+    // Python modules generated by pyrcc or pyside-rcc contain the arrays as bytes literals.
+    if let Some((bytes, symbols)) = python::parse_module(&bytes) {
+        return extract_source(&output, &bytes, &symbols);
+    }
+
+    // Minidumps contain the memory ranges of the process at the time it crashed.
+    if minidump::is_minidump(&bytes) {
+        let mapping = minidump::parse(&bytes)?;
+
+        return extract(&output, &bytes, &mapping);
+    }
+
+    // Universal binaries contain a Mach-O image for every architecture. Analyse each slice on its
+    // own and extract its resources to a separate directory.
+    let slices = executable::fat_slices(&bytes)?;
+
+    if !slices.is_empty() {
+        for (name, range) in slices {
+            println!("Found {} slice at 0x{:x}-0x{:x}...", name, range.start, range.end);
+
+            // Truncated or malformed binaries may have slices that extend past the end of the file.
+            let slice = match bytes.get(range) {
+                Some(slice) => slice,
+                _ => {
+                    println!("Slice {} is out of bounds, skipping...", name);
+                    continue;
+                }
+            };
+
+            let mapping = ExecutableMapping::parse(slice)?;
+
+            extract(&output.join(name), slice, &mapping)?;
+        }
+
+        return Ok(());
+    }
+
+    // Android packages store the native libraries, and therefore the resources, as
+    // `lib/<abi>/<name>.so`. Analyse each library on its own and extract its resources to a
+    // separate directory.
+    if apk::is_zip(&bytes) {
+        for (abi, name, library) in apk::native_libraries(&bytes)? {
+            println!("Found native library {}/{}...", abi, name);
+
+            // A single library that can't be parsed shouldn't keep us from analysing the others.
+            let mapping = match ExecutableMapping::parse(&library) {
+                Ok(mapping) => mapping,
+                Err(error) => {
+                    println!("Failed to parse native library {}/{}: {}", abi, name, error);
+                    continue;
+                }
+            };
+
+            extract(&output.join(abi).join(name), &library, &mapping)?;
+        }
+
+        return Ok(());
+    }
+
+    let mapping = ExecutableMapping::parse(&bytes)?;
+
+    extract(&output, &bytes, &mapping)
+}
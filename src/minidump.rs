@@ -0,0 +1,352 @@
+use anyhow::{Context, Result};
+use binrw::BinRead;
+use binrw::io::Cursor;
+
+use crate::executable::ExecutableMapping;
+
+/// The stream type of the stream listing the loaded modules.
+const MODULE_LIST_STREAM: u32 = 4;
+
+/// The stream type of the stream listing the dumped memory ranges.
+const MEMORY_LIST_STREAM: u32 = 5;
+
+/// The stream type of the stream listing the dumped memory ranges of full memory dumps.
+const MEMORY64_LIST_STREAM: u32 = 9;
+
+#[derive(BinRead, Debug)]
+#[br(little, magic = b"MDMP")]
+pub struct Header {
+    _version: u32,
+    stream_count: u32,
+    stream_directory_rva: u32,
+    _checksum: u32,
+    _timestamp: u32,
+    _flags: u64,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct Directory {
+    stream_type: u32,
+    _data_size: u32,
+    rva: u32,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct MemoryDescriptor {
+    start: u64,
+    data_size: u32,
+    rva: u32,
+}
+
+/// The size of a memory descriptor in the memory list stream.
+const MEMORY_DESCRIPTOR_SIZE: u64 = 16;
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct MemoryList {
+    count: u32,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct MemoryDescriptor64 {
+    start: u64,
+    data_size: u64,
+}
+
+/// The size of a memory descriptor in the memory64 list stream.
+const MEMORY_DESCRIPTOR64_SIZE: u64 = 16;
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct Memory64List {
+    count: u64,
+    base_rva: u64,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct Module {
+    base: u64,
+    size: u32,
+    _checksum: u32,
+    _timestamp: u32,
+    name_rva: u32,
+    _version_info: [u32; 13],
+    _cv_record: [u32; 2],
+    _misc_record: [u32; 2],
+    _reserved: [u64; 2],
+}
+
+/// The size of a module in the module list stream.
+const MODULE_SIZE: u64 = 108;
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct ModuleList {
+    count: u32,
+}
+
+/// Checks whether the given byte array is a minidump.
+pub fn is_minidump(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"MDMP")
+}
+
+/// Reads the UTF-16 LE string, prefixed with its 32-bit size in bytes, at the given offset.
+fn read_string(bytes: &[u8], offset: usize) -> Option<String> {
+    let mut slice = [0u8; 4];
+    slice.copy_from_slice(bytes.get(offset..)?.get(..4)?);
+    let size = u32::from_le_bytes(slice) as usize;
+
+    let name: Vec<u16> = bytes.get(offset + 4..)?.get(..size)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    String::from_utf16(&name).ok()
+}
+
+/// Checks that the `count` entries of `size` bytes each that follow the list header, which ends at
+/// the given offset, fit in the byte array. The counts are taken straight from the file, so they
+/// must not be trusted to allocate or read the entries.
+fn check_count(bytes: &[u8], offset: u64, count: u64, size: u64) -> Result<()> {
+    let end = count.checked_mul(size).and_then(|entries| entries.checked_add(offset));
+
+    if end.is_none_or(|end| end > bytes.len() as u64) {
+        anyhow::bail!("the list holds more entries than fit in the minidump.");
+    }
+
+    Ok(())
+}
+
+/// Maps the memory range of `size` bytes at the virtual address `start` to the place where it is
+/// stored in the given byte array at the file offset `offset`. Ranges that run past the end of
+/// the byte array, e.g. of truncated minidumps, are clipped.
+fn insert_range(mapping: &mut ExecutableMapping, bytes: &[u8], offset: u64, start: u64, size: u64) {
+    let size = size.min((bytes.len() as u64).saturating_sub(offset));
+
+    if size == 0 {
+        return;
+    }
+
+    mapping.insert(offset as usize..(offset + size) as usize, start as usize..start.saturating_add(size) as usize);
+}
+
+/// Parses the minidump in the given byte array. The memory ranges listed in the memory list
+/// streams are mapped to the place where they are stored in the minidump, whereas the module list
+/// stream tells us what module each address belongs to.
+///
+/// Yields the mapping.
+pub fn parse(bytes: &[u8]) -> Result<ExecutableMapping> {
+    let mut mapping = ExecutableMapping::new();
+    let mut reader = Cursor::new(bytes);
+
+    let header = Header::read(&mut reader)
+        .context("could not parse the minidump header.")?;
+
+    for index in 0..header.stream_count as usize {
+        reader.set_position(header.stream_directory_rva as u64 + index as u64 * 12);
+
+        let directory = Directory::read(&mut reader)
+            .context("could not parse the minidump stream directory.")?;
+
+        reader.set_position(directory.rva as u64);
+
+        match directory.stream_type {
+            MEMORY_LIST_STREAM => {
+                let list = MemoryList::read(&mut reader)
+                    .context("could not parse the memory list stream.")?;
+
+                check_count(bytes, reader.position(), list.count as u64, MEMORY_DESCRIPTOR_SIZE)
+                    .context("could not parse the memory list stream.")?;
+
+                for _ in 0..list.count {
+                    let range = MemoryDescriptor::read(&mut reader)
+                        .context("could not parse the memory list stream.")?;
+
+                    insert_range(&mut mapping, bytes, range.rva as u64, range.start, range.data_size as u64);
+                }
+            }
+            MEMORY64_LIST_STREAM => {
+                let list = Memory64List::read(&mut reader)
+                    .context("could not parse the memory64 list stream.")?;
+
+                check_count(bytes, reader.position(), list.count, MEMORY_DESCRIPTOR64_SIZE)
+                    .context("could not parse the memory64 list stream.")?;
+
+                // The memory ranges are stored consecutively starting at the base RVA.
+                let mut offset = list.base_rva;
+
+                for _ in 0..list.count {
+                    let range = MemoryDescriptor64::read(&mut reader)
+                        .context("could not parse the memory64 list stream.")?;
+
+                    insert_range(&mut mapping, bytes, offset, range.start, range.data_size);
+                    offset = offset.saturating_add(range.data_size);
+                }
+            }
+            MODULE_LIST_STREAM => {
+                let list = ModuleList::read(&mut reader)
+                    .context("could not parse the module list stream.")?;
+
+                check_count(bytes, reader.position(), list.count as u64, MODULE_SIZE)
+                    .context("could not parse the module list stream.")?;
+
+                for _ in 0..list.count {
+                    let module = Module::read(&mut reader)
+                        .context("could not parse the module list stream.")?;
+
+                    let name = match read_string(bytes, module.name_rva as usize) {
+                        Some(name) => name,
+                        _ => continue,
+                    };
+
+                    let rva_range = module.base as usize..module.base as usize + module.size as usize;
+
+                    mapping.insert_module(rva_range, name);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends the given 32-bit values in little endian byte order.
+    fn push_u32(bytes: &mut Vec<u8>, values: &[u32]) {
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Appends the given 64-bit values in little endian byte order.
+    fn push_u64(bytes: &mut Vec<u8>, values: &[u64]) {
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Builds a minidump with a memory list, a memory64 list and a module list stream.
+    fn minidump() -> Vec<u8> {
+        // The header is followed by the stream directory at 0x20 and the streams at 0x100.
+        let mut bytes = b"MDMP".to_vec();
+        push_u32(&mut bytes, &[0xa793, 3, 0x20, 0, 0]);
+        push_u64(&mut bytes, &[0]);
+
+        push_u32(&mut bytes, &[MEMORY_LIST_STREAM, 0x14, 0x100]);
+        push_u32(&mut bytes, &[MEMORY64_LIST_STREAM, 0x30, 0x200]);
+        push_u32(&mut bytes, &[MODULE_LIST_STREAM, 0x70, 0x300]);
+
+        // A single memory range of 0x10 bytes at 0x10000, stored at 0x400.
+        bytes.resize(0x100, 0);
+        push_u32(&mut bytes, &[1]);
+        push_u64(&mut bytes, &[0x10000]);
+        push_u32(&mut bytes, &[0x10, 0x400]);
+
+        // Two consecutive memory ranges at 0x20000 and 0x30000, stored at 0x500.
+        bytes.resize(0x200, 0);
+        push_u64(&mut bytes, &[2, 0x500, 0x20000, 0x20, 0x30000, 0x8]);
+
+        // A single module covering the second memory range.
+        bytes.resize(0x300, 0);
+        push_u32(&mut bytes, &[1]);
+        push_u64(&mut bytes, &[0x20000]);
+        push_u32(&mut bytes, &[0x1000, 0, 0, 0x380]);
+        bytes.resize(bytes.len() + 13 * 4 + 2 * 4 + 2 * 4 + 2 * 8, 0);
+
+        // The name of the module.
+        bytes.resize(0x380, 0);
+        let name: Vec<u16> = "C:\\app.exe".encode_utf16().collect();
+        push_u32(&mut bytes, &[name.len() as u32 * 2]);
+
+        for c in name {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+
+        bytes.resize(0x528, 0);
+        bytes
+    }
+
+    #[test]
+    fn parses_memory_and_module_lists() {
+        let bytes = minidump();
+        assert!(is_minidump(&bytes));
+
+        let mapping = parse(&bytes).unwrap();
+
+        // The memory list.
+        assert_eq!(mapping.rva_to_file_offset(0x10000), Some(0x400));
+        assert_eq!(mapping.rva_to_file_offset(0x1000f), Some(0x40f));
+        assert_eq!(mapping.rva_to_file_offset(0x10010), None);
+
+        // The memory64 list.
+        assert_eq!(mapping.rva_to_file_offset(0x20000), Some(0x500));
+        assert_eq!(mapping.rva_to_file_offset(0x30004), Some(0x524));
+        assert_eq!(mapping.file_offset_to_rva(0x520), Some(0x30000));
+
+        // The module list.
+        assert_eq!(mapping.module_at(0x510), Some("C:\\app.exe"));
+        assert_eq!(mapping.module_at(0x520), None);
+    }
+
+    #[test]
+    fn rejects_truncated_streams() {
+        let mut bytes = minidump();
+        bytes.truncate(0x310);
+
+        assert!(parse(&bytes).is_err());
+        assert!(parse(b"MDMP").is_err());
+    }
+
+    #[test]
+    fn reads_module_names() {
+        let mut bytes = vec![];
+        push_u32(&mut bytes, &[4]);
+        bytes.extend_from_slice(&[b'a', 0, b'b', 0]);
+
+        assert_eq!(read_string(&bytes, 0).as_deref(), Some("ab"));
+        assert_eq!(read_string(&bytes, 2), None);
+        assert_eq!(read_string(&bytes[..6], 0), None);
+    }
+
+    #[test]
+    fn rejects_oversized_counts() {
+        // The memory64 list claims to hold the maximum number of ranges.
+        let mut bytes = minidump();
+        bytes[0x200..0x208].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(parse(&bytes).is_err());
+
+        // The memory list claims to hold the maximum number of ranges.
+        let mut bytes = minidump();
+        bytes[0x100..0x104].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn clips_ranges_to_the_file() {
+        // The second memory64 range at 0x520 runs past the end of the file.
+        let mut bytes = minidump();
+        bytes.truncate(0x524);
+
+        let mapping = parse(&bytes).unwrap();
+
+        assert_eq!(mapping.rva_to_file_offset(0x30003), Some(0x523));
+        assert_eq!(mapping.rva_to_file_offset(0x30004), None);
+
+        // The memory range at 0x400 lies past the end of the file.
+        bytes[0x110..0x114].copy_from_slice(&0x1000u32.to_le_bytes());
+
+        let mapping = parse(&bytes).unwrap();
+
+        assert_eq!(mapping.rva_to_file_offset(0x10000), None);
+    }
+}
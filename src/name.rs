@@ -112,7 +112,8 @@ pub fn parse_names(
     let mut names = BTreeMap::new();
     let mut name = vec![];
 
-    while offset < bytes.len() {
+    // Check that we have enough bytes for the 16-bit size and the 32-bit hash.
+    while bytes.len() - offset >= 6 {
         // Decode the 16-bit size.
         let mut slice = [0u8; 2];
         slice.copy_from_slice(&bytes[offset..][..2]);
@@ -129,6 +130,11 @@ pub fn parse_names(
         let hash = u32::from_be_bytes(slice);
         offset += 4;
 
+        // Check that we have enough bytes for the string.
+        if bytes.len() - offset < 2 * size {
+            break;
+        }
+
         // Decode the UTF-16 BE string.
         name.clear();
 
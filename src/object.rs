@@ -0,0 +1,521 @@
+use anyhow::Result;
+use goblin::Object;
+use goblin::archive::Archive;
+use goblin::elf::header::ET_REL;
+use goblin::mach::Mach;
+use goblin::mach::symbols::{NO_SECT, N_STAB};
+use goblin::pe::Coff;
+use goblin::pe::header::{COFF_MACHINE_ARM64, COFF_MACHINE_ARMNT, COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::executable::ExecutableMapping;
+use crate::symbols::{group_symbols, unqualified_name, ResourceSymbols};
+
+/// The prefix of the functions that rcc emits to register the resources of a qrc file.
+const INITIALIZER_PREFIX: &str = "qInitResources_";
+
+/// The size to assume for initializers whose size is not known.
+const INITIALIZER_SIZE: usize = 0x100;
+
+/// Checks whether the given byte array is a static archive (`.a` or `.lib`).
+pub fn is_archive(bytes: &[u8]) -> bool {
+    bytes.starts_with(goblin::archive::MAGIC)
+}
+
+/// The size of the COFF file header.
+const COFF_HEADER_SIZE: usize = 20;
+
+/// The size of a COFF section header.
+const COFF_SECTION_HEADER_SIZE: usize = 40;
+
+/// Checks whether the given byte array is a COFF object as produced by MSVC. Since COFF objects
+/// lack a magic, we check that the machine is known and that the section headers, which follow the
+/// file header and the optional header, lie within the byte array.
+fn is_coff(bytes: &[u8]) -> bool {
+    if bytes.len() < COFF_HEADER_SIZE {
+        return false;
+    }
+
+    let machine = u16::from_le_bytes([bytes[0], bytes[1]]);
+
+    if !matches!(machine, COFF_MACHINE_X86 | COFF_MACHINE_X86_64 | COFF_MACHINE_ARMNT | COFF_MACHINE_ARM64) {
+        return false;
+    }
+
+    let section_count = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+    let optional_header_size = u16::from_le_bytes([bytes[16], bytes[17]]) as usize;
+
+    if section_count == 0 {
+        return false;
+    }
+
+    COFF_HEADER_SIZE + optional_header_size + section_count * COFF_SECTION_HEADER_SIZE <= bytes.len()
+}
+
+/// Parses the static archive in the given byte array. Yields the name and the contents of every
+/// member. Members are named after their file name without any directories, and names that occur
+/// more than once get the index of the member appended, such that every member can be extracted to
+/// its own directory.
+pub fn archive_members(bytes: &[u8]) -> Result<Vec<(String, &[u8])>> {
+    let archive = Archive::parse(bytes)?;
+    let mut members = vec![];
+
+    for index in 0..archive.len() {
+        let member = match archive.get_at(index) {
+            Some(member) => member,
+            _ => continue,
+        };
+
+        let start = member.offset as usize;
+        let end = start + member.size();
+
+        // MSVC stores the full path of the object in the name.
+        let name = member.extended_name()
+            .rsplit(['/', '\\'])
+            .find(|name| !name.is_empty() && *name != "." && *name != "..")
+            .unwrap_or("member");
+
+        if let Some(member_bytes) = bytes.get(start..end) {
+            members.push((name.to_string(), index, member_bytes));
+        }
+    }
+
+    // Disambiguate the members that share a name.
+    let mut counts = HashMap::new();
+
+    for (name, _, _) in &members {
+        *counts.entry(name.clone()).or_insert(0) += 1;
+    }
+
+    Ok(members
+        .into_iter()
+        .map(|(name, index, member_bytes)| match counts[&name] {
+            1 => (name, member_bytes),
+            _ => (format!("{}.{}", name, index), member_bytes),
+        })
+        .collect())
+}
+
+/// Looks up the arrays emitted by rcc in the symbol table of the relocatable ELF or COFF object in
+/// the given byte array. Since the object has not been linked yet, the symbols are relative to the
+/// section they are defined in, which means we can resolve them to file offsets directly.
+///
+/// Yields the sets of arrays that were found, or an empty list if the byte array is not a
+/// relocatable object.
+pub fn resource_symbols(bytes: &[u8]) -> Result<Vec<ResourceSymbols>> {
+    let mut symbols = vec![];
+
+    match Object::parse(bytes)? {
+        Object::Elf(elf) if elf.header.e_type == ET_REL => {
+            for sym in elf.syms.iter() {
+                let name = match elf.strtab.get_at(sym.st_name) {
+                    Some(name) => name,
+                    _ => continue,
+                };
+
+                // Undefined symbols refer to arrays of other objects.
+                if sym.st_shndx == 0 {
+                    continue;
+                }
+
+                let section = match elf.section_headers.get(sym.st_shndx) {
+                    Some(section) => section,
+                    _ => continue,
+                };
+
+                symbols.push((name, section.sh_offset as usize + sym.st_value as usize));
+            }
+        }
+        Object::Unknown(_) if is_coff(bytes) => {
+            let coff = Coff::parse(bytes)?;
+
+            for (_, name, symbol) in coff.symbols.iter() {
+                // Long names are stored in the string table.
+                let name = match name.or_else(|| coff.strings.get_at(symbol.name_offset()? as usize)) {
+                    Some(name) => name,
+                    _ => continue,
+                };
+
+                // Section numbers are one-based, and zero or negative numbers have a special
+                // meaning.
+                if symbol.section_number <= 0 {
+                    continue;
+                }
+
+                let section = match coff.sections.get(symbol.section_number as usize - 1) {
+                    Some(section) => section,
+                    _ => continue,
+                };
+
+                symbols.push((name, section.pointer_to_raw_data as usize + symbol.value as usize));
+            }
+        }
+        _ => (),
+    }
+
+    Ok(group_symbols(symbols))
+}
+
+/// Reads the symbol table of the linked ELF, Mach-O or PE image in the given byte array. ELF and
+/// Mach-O symbols hold virtual addresses, which are resolved to file offsets through the mapping
+/// `mapping`, whereas the COFF symbols that MinGW leaves in PE images are relative to the section
+/// they are defined in. PE images also contribute their exports.
+///
+/// Yields the name, the file offset and the size of every defined symbol, where the size is zero if
+/// it is not known.
+fn image_symbol_table<'a>(
+    bytes: &'a [u8],
+    mapping: &ExecutableMapping,
+) -> Result<Vec<(&'a str, usize, usize)>> {
+    let mut symbols = vec![];
+
+    match Object::parse(bytes)? {
+        Object::Elf(elf) if elf.header.e_type != ET_REL => {
+            let tables = [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)];
+
+            for (syms, strtab) in tables {
+                for sym in syms.iter() {
+                    if sym.st_shndx == 0 || sym.st_value == 0 {
+                        continue;
+                    }
+
+                    let name = match strtab.get_at(sym.st_name) {
+                        Some(name) => name,
+                        _ => continue,
+                    };
+
+                    let offset = match mapping.rva_to_file_offset(sym.st_value as usize) {
+                        Some(offset) => offset,
+                        _ => continue,
+                    };
+
+                    symbols.push((name, offset, sym.st_size as usize));
+                }
+            }
+        }
+        Object::Mach(Mach::Binary(macho)) => {
+            for symbol in macho.symbols() {
+                let (name, nlist) = match symbol {
+                    Ok(symbol) => symbol,
+                    _ => break,
+                };
+
+                // Skip the debugging symbols and the undefined symbols.
+                if nlist.n_type & N_STAB != 0 || nlist.n_sect == NO_SECT as usize {
+                    continue;
+                }
+
+                let offset = match mapping.rva_to_file_offset(nlist.n_value as usize) {
+                    Some(offset) => offset,
+                    _ => continue,
+                };
+
+                symbols.push((name, offset, 0));
+            }
+        }
+        Object::PE(pe) => {
+            for export in &pe.exports {
+                if let (Some(name), Some(offset)) = (export.name, export.offset) {
+                    symbols.push((name, offset, 0));
+                }
+            }
+
+            let coff_header = &pe.header.coff_header;
+
+            if coff_header.pointer_to_symbol_table == 0 {
+                return Ok(symbols);
+            }
+
+            let (table, strings) = match (coff_header.symbols(bytes), coff_header.strings(bytes)) {
+                (Ok(table), Ok(strings)) => (table, strings),
+                _ => return Ok(symbols),
+            };
+
+            for (_, name, symbol) in table.iter() {
+                // Long names are stored in the string table.
+                let name = match name.or_else(|| strings.get_at(symbol.name_offset()? as usize)) {
+                    Some(name) => name,
+                    _ => continue,
+                };
+
+                // Section numbers are one-based, and zero or negative numbers have a special
+                // meaning.
+                if symbol.section_number <= 0 {
+                    continue;
+                }
+
+                let section = match pe.sections.get(symbol.section_number as usize - 1) {
+                    Some(section) => section,
+                    _ => continue,
+                };
+
+                symbols.push((name, section.pointer_to_raw_data as usize + symbol.value as usize, 0));
+            }
+        }
+        _ => (),
+    }
+
+    Ok(symbols)
+}
+
+/// Looks up the arrays emitted by rcc in the symbol table of the linked ELF, Mach-O or PE image in
+/// the given byte array. Unless the image has been stripped, the symbol table still holds the
+/// local symbols of the arrays.
+///
+/// Yields the sets of arrays that were found, or an empty list if the byte array is not an image or
+/// if the image lacks the symbols.
+pub fn image_symbols(bytes: &[u8], mapping: &ExecutableMapping) -> Result<Vec<ResourceSymbols>> {
+    let symbols = image_symbol_table(bytes, mapping)?;
+
+    Ok(group_symbols(symbols.into_iter().map(|(name, offset, _)| (name, offset))))
+}
+
+/// Looks up the `qInitResources_<name>` functions that rcc emits for every qrc file in the symbol
+/// table of the linked ELF, Mach-O or PE image in the given byte array. Functions of unknown size
+/// are assumed to end at the next symbol, but no later than `INITIALIZER_SIZE` bytes after their
+/// start.
+///
+/// Yields the name of the qrc file and the file range of the function of every initializer.
+pub fn initializers(bytes: &[u8], mapping: &ExecutableMapping) -> Result<Vec<(String, Range<usize>)>> {
+    let symbols = image_symbol_table(bytes, mapping)?;
+    let mut initializers = vec![];
+
+    // Collect the ordered symbol offsets.
+    let mut offsets: Vec<usize> = symbols.iter().map(|(_, offset, _)| *offset).collect();
+    offsets.sort();
+
+    for (name, offset, size) in symbols {
+        let name = match unqualified_name(name).strip_prefix(INITIALIZER_PREFIX) {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+
+        let end = if size != 0 {
+            offset + size
+        } else {
+            // Find the next symbol.
+            let next = match offsets.binary_search(&(offset + 1)) {
+                Ok(index) | Err(index) => offsets.get(index).copied(),
+            };
+
+            next.unwrap_or(usize::MAX).min(offset + INITIALIZER_SIZE)
+        };
+
+        initializers.push((name.to_string(), offset..end.min(bytes.len())));
+    }
+
+    Ok(initializers)
+}
+
+/// Looks up the function `name`, e.g. `qRegisterResourceData`, in the imports of the linked ELF,
+/// Mach-O or PE image in the given byte array. Imported functions are called through a pointer slot,
+/// e.g. an entry of the GOT or the IAT, which the dynamic linker fills in at load time.
+///
+/// Yields the virtual addresses of the pointer slots of the function.
+pub fn import_slots(bytes: &[u8], mapping: &ExecutableMapping, name: &str) -> Result<Vec<usize>> {
+    let mut slots = vec![];
+
+    match Object::parse(bytes)? {
+        Object::Elf(elf) => {
+            // The slots are filled in through the dynamic relocations.
+            for relocs in [&elf.pltrelocs, &elf.dynrelas, &elf.dynrels] {
+                for reloc in relocs.iter() {
+                    let sym = match elf.dynsyms.get(reloc.r_sym) {
+                        Some(sym) => sym,
+                        _ => continue,
+                    };
+
+                    match elf.dynstrtab.get_at(sym.st_name) {
+                        Some(sym_name) if unqualified_name(sym_name) == name => {
+                            slots.push(reloc.r_offset as usize);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+        Object::Mach(Mach::Binary(macho)) => {
+            for import in macho.imports()? {
+                if unqualified_name(import.name) == name {
+                    slots.push(import.address as usize);
+                }
+            }
+        }
+        Object::PE(pe) => {
+            // The offset of the import is the relative virtual address of its IAT entry.
+            for import in &pe.imports {
+                if unqualified_name(&import.name) == name {
+                    slots.push(pe.image_base + import.offset);
+                }
+            }
+        }
+        _ => (),
+    }
+
+    // Make sure the slots are actually mapped.
+    slots.retain(|slot| mapping.rva_to_file_offset(*slot).is_some());
+
+    Ok(slots)
+}
+
+/// Looks up the function `name` in the symbol table of the linked ELF, Mach-O or PE image in the
+/// given byte array, e.g. when Qt was linked statically.
+///
+/// Yields the virtual addresses of the definitions of the function.
+pub fn function_addresses(bytes: &[u8], mapping: &ExecutableMapping, name: &str) -> Result<Vec<usize>> {
+    let addresses = image_symbol_table(bytes, mapping)?
+        .into_iter()
+        .filter(|(symbol, _, _)| unqualified_name(symbol) == name)
+        .filter_map(|(_, offset, _)| mapping.file_offset_to_rva(offset))
+        .collect();
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a COFF file header with the given machine, section count and optional header size.
+    fn coff_header(machine: u16, section_count: u16, optional_header_size: u16) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&machine.to_le_bytes());
+        bytes.extend_from_slice(&section_count.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(&optional_header_size.to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes
+    }
+
+    /// Encodes a GNU archive with the given member names and contents.
+    fn archive(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut bytes = goblin::archive::MAGIC.to_vec();
+
+        for (name, contents) in members {
+            bytes.extend_from_slice(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, 0, 0, 0, 644, contents.len()).as_bytes());
+            bytes.extend_from_slice(contents);
+
+            if !bytes.len().is_multiple_of(2) {
+                bytes.push(b'\n');
+            }
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn detects_coff_objects() {
+        let mut bytes = coff_header(COFF_MACHINE_X86_64, 2, 0);
+        bytes.resize(COFF_HEADER_SIZE + 2 * COFF_SECTION_HEADER_SIZE, 0);
+        assert!(is_coff(&bytes));
+
+        // Unknown machines.
+        let mut bytes = coff_header(0x1234, 2, 0);
+        bytes.resize(COFF_HEADER_SIZE + 2 * COFF_SECTION_HEADER_SIZE, 0);
+        assert!(!is_coff(&bytes));
+
+        // No sections.
+        assert!(!is_coff(&coff_header(COFF_MACHINE_X86_64, 0, 0)));
+
+        // Section headers or optional headers past the end.
+        let mut bytes = coff_header(COFF_MACHINE_ARM64, 3, 0);
+        bytes.resize(COFF_HEADER_SIZE + 2 * COFF_SECTION_HEADER_SIZE, 0);
+        assert!(!is_coff(&bytes));
+
+        let mut bytes = coff_header(COFF_MACHINE_ARM64, 2, 0xe0);
+        bytes.resize(COFF_HEADER_SIZE + 2 * COFF_SECTION_HEADER_SIZE, 0);
+        assert!(!is_coff(&bytes));
+
+        assert!(!is_coff(&bytes[..10]));
+    }
+
+    #[test]
+    fn disambiguates_archive_members() {
+        let bytes = archive(&[
+            ("qrc.o/", b"first"),
+            ("obj\\qrc.o/", b"second"),
+            ("main.o/", b"third"),
+            ("qrc.o/", b"fourth"),
+        ]);
+
+        let members = archive_members(&bytes).unwrap();
+
+        assert_eq!(members, vec![
+            ("qrc.o.0".to_string(), &b"first"[..]),
+            ("qrc.o.1".to_string(), &b"second"[..]),
+            ("main.o".to_string(), &b"third"[..]),
+            ("qrc.o.3".to_string(), &b"fourth"[..]),
+        ]);
+    }
+
+    /// Encodes a 64-bit little endian relocatable ELF object with a `.rodata` section at 0x40 and
+    /// the given symbols, which consist of a name, a section index and a value.
+    fn elf_object(symbols: &[(&str, u16, u64)]) -> Vec<u8> {
+        let mut strtab = vec![0];
+        let mut symtab = vec![0; 24];
+
+        for (name, section, value) in symbols {
+            symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            symtab.extend_from_slice(&[1, 0]);
+            symtab.extend_from_slice(&section.to_le_bytes());
+            symtab.extend_from_slice(&[*value, 0].map(u64::to_le_bytes).concat());
+
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        // The sections are followed by their headers.
+        let strtab_offset = 0x80;
+        let symtab_offset = strtab_offset + strtab.len() as u64;
+        let headers_offset = symtab_offset + symtab.len() as u64;
+
+        let mut bytes = b"\x7fELF\x02\x01\x01".to_vec();
+        bytes.resize(16, 0);
+        bytes.extend_from_slice(&[ET_REL, 62].map(u16::to_le_bytes).concat());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, headers_offset].map(u64::to_le_bytes).concat());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[0x40u16, 0x38, 0, 0x40, 4, 0].map(u16::to_le_bytes).concat());
+
+        bytes.resize(strtab_offset as usize, 0);
+        bytes.extend_from_slice(&strtab);
+        bytes.extend_from_slice(&symtab);
+
+        // The null section, .rodata, .symtab and .strtab.
+        let sections = [
+            (0, 0, 0, 0, 0),
+            (1, 0x40, 0x40, 0, 0),
+            (2, symtab_offset, symtab.len() as u64, 3, 24),
+            (3, strtab_offset, strtab.len() as u64, 0, 0),
+        ];
+
+        for (kind, offset, size, link, entry_size) in sections {
+            bytes.extend_from_slice(&[0u32, kind].map(u32::to_le_bytes).concat());
+            bytes.extend_from_slice(&[0, 0, offset, size].map(u64::to_le_bytes).concat());
+            bytes.extend_from_slice(&[link, 1].map(u32::to_le_bytes).concat());
+            bytes.extend_from_slice(&[1, entry_size].map(u64::to_le_bytes).concat());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn skips_undefined_elf_symbols() {
+        let bytes = elf_object(&[
+            ("qt_resource_data", 0, 0),
+            ("qt_resource_struct", 1, 0x10),
+            ("qt_resource_name", 1, 0x20),
+        ]);
+
+        let symbols = resource_symbols(&bytes).unwrap();
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!((symbols[0].tree, symbols[0].names, symbols[0].data), (Some(0x50), Some(0x60), None));
+    }
+
+    #[test]
+    fn ignores_unknown_objects() {
+        assert!(resource_symbols(b"not an object").unwrap_or_default().is_empty());
+    }
+}
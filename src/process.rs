@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::executable::ExecutableMapping;
+
+/// The size above which regions that are not backed by a file are skipped. Such regions are
+/// usually heaps or reserved address space, which can grow far larger than any resource image.
+const MAX_ANONYMOUS_REGION_SIZE: usize = 256 << 20;
+
+/// Reads the memory of the running process with the given PID through `/proc/<pid>/maps` and
+/// `/proc/<pid>/mem`. Every readable region is copied into a single byte array, such that the
+/// usual heuristics can be applied to it, while the mapping keeps track of where each region was
+/// located in the address space of the process and what file was mapped there. Regions that are
+/// not backed by a file and exceed `MAX_ANONYMOUS_REGION_SIZE` are skipped.
+///
+/// Yields the byte array and the mapping.
+pub fn read_process(pid: u32) -> Result<(Vec<u8>, ExecutableMapping)> {
+    let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid))
+        .with_context(|| format!("could not read the memory map of process {}.", pid))?;
+
+    let mut mem = File::open(format!("/proc/{}/mem", pid))
+        .with_context(|| format!("could not open the memory of process {}.", pid))?;
+
+    let mut bytes = vec![];
+    let mut mapping = ExecutableMapping::new();
+
+    for line in maps.lines() {
+        // Each line is of the form `start-end perms offset dev inode [path]`.
+        let mut fields = line.split_whitespace();
+
+        let (range, perms) = match (fields.next(), fields.next()) {
+            (Some(range), Some(perms)) => (range, perms),
+            _ => continue,
+        };
+
+        let path = fields.nth(3);
+
+        // Decode the address range.
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start, end),
+            _ => continue,
+        };
+
+        let (start, end) = match (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16)) {
+            (Ok(start), Ok(end)) if start < end => (start, end),
+            _ => continue,
+        };
+
+        // We can only read the regions that are readable.
+        if !perms.starts_with('r') {
+            continue;
+        }
+
+        let path = path.filter(|path| path.starts_with('/'));
+
+        if path.is_none() && end - start > MAX_ANONYMOUS_REGION_SIZE {
+            println!("Skipping anonymous region at 0x{:x}-0x{:x}...", start, end);
+            continue;
+        }
+
+        // Read the region directly into the byte array. Some regions such as [vvar] cannot be
+        // read, so just skip them.
+        let offset = bytes.len();
+        bytes.resize(offset + (end - start), 0);
+
+        if mem.seek(SeekFrom::Start(start as u64)).is_err() || mem.read_exact(&mut bytes[offset..]).is_err() {
+            bytes.truncate(offset);
+            continue;
+        }
+
+        // Track where the region was located in the address space of the process.
+        mapping.insert(offset..bytes.len(), start..end);
+
+        if perms.contains('x') {
+            mapping.insert_code(offset..bytes.len());
+        }
+
+        // Track the file that was mapped there.
+        if let Some(path) = path {
+            mapping.insert_module(start..end, path.to_string());
+        }
+    }
+
+    Ok((bytes, mapping))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name::hash_str;
+    use crate::qres::{extract_qres, find_images};
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+
+    const CONTENTS: &[u8] = b"resource read from another process";
+
+    /// Builds a binary resource image of version 2 with a single file `file.txt` in its root.
+    fn image() -> Vec<u8> {
+        let mut names = vec![];
+
+        for name in ["root", "file.txt"] {
+            names.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            names.extend_from_slice(&hash_str(name).to_be_bytes());
+
+            for c in name.encode_utf16() {
+                names.extend_from_slice(&c.to_be_bytes());
+            }
+        }
+
+        // The root directory and the file, using the layout of version 2.
+        let mut tree = vec![];
+
+        for (name_offset, flags, a, b) in [(0u32, 2u16, 1u32, 1u32), (14, 0, 0, 0)] {
+            tree.extend_from_slice(&name_offset.to_be_bytes());
+            tree.extend_from_slice(&flags.to_be_bytes());
+            tree.extend_from_slice(&a.to_be_bytes());
+            tree.extend_from_slice(&b.to_be_bytes());
+            tree.extend_from_slice(&0u64.to_be_bytes());
+        }
+
+        let mut data = (CONTENTS.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(CONTENTS);
+
+        let tree_offset = 20;
+        let data_offset = tree_offset + tree.len();
+        let names_offset = data_offset + data.len();
+
+        let mut bytes = b"qres".to_vec();
+
+        for value in [2, tree_offset, data_offset, names_offset] {
+            bytes.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+
+        bytes.extend(tree);
+        bytes.extend(data);
+        bytes.extend(names);
+        bytes
+    }
+
+    /// Not a test by itself, but the child process for `reads_resources_of_running_processes`. It
+    /// keeps a binary resource image in memory until its standard input is closed.
+    #[test]
+    #[ignore]
+    fn child_process() {
+        if std::env::var_os("QTRC_EXTRACT_CHILD").is_none() {
+            return;
+        }
+
+        let image = image();
+
+        println!("ready");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).unwrap();
+
+        assert!(!image.is_empty());
+    }
+
+    #[test]
+    fn reads_resources_of_running_processes() {
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "process::tests::child_process", "--ignored", "--nocapture"])
+            .env("QTRC_EXTRACT_CHILD", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // Wait until the child has built the image. The test harness prints the name of the test on
+        // the same line.
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        assert!(lines.any(|line| line.unwrap().ends_with("ready")));
+
+        let result = read_process(child.id());
+
+        drop(child.stdin.take());
+        child.wait().unwrap();
+
+        let (bytes, mapping) = result.unwrap();
+        assert!(!mapping.code_ranges().is_empty());
+
+        // Extract every copy of the image that we can find.
+        let root = std::env::temp_dir().join(format!("qtrc-extract-process-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let images = find_images(&bytes);
+        assert!(!images.is_empty());
+
+        for (offset, (_, header)) in images {
+            assert!(mapping.file_offset_to_rva(offset).is_some());
+            extract_qres(root.join(format!("{:x}", offset)), &bytes[offset..], &header).unwrap();
+
+            assert_eq!(std::fs::read(root.join(format!("{:x}/root/file.txt", offset))).unwrap(), CONTENTS);
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
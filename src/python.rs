@@ -0,0 +1,198 @@
+use crate::literal::{decode_escape, parse_integer, Dialect};
+use crate::symbols::ResourceSymbols;
+
+/// Skips whitespace, line continuations and parentheses starting at the given offset. Yields the
+/// offset of the next token.
+fn skip_whitespace(source: &[u8], mut offset: usize) -> usize {
+    while offset < source.len() {
+        match source[offset] {
+            b' ' | b'\t' | b'\r' | b'\n' | b'(' | b')' => offset += 1,
+            b'\\' if matches!(source.get(offset + 1), Some(b'\n') | Some(b'\r')) => offset += 1,
+            _ => break,
+        }
+    }
+
+    offset
+}
+
+/// Parses a sequence of adjacent bytes literals of the form `b"\x00\x01..." b"..."` starting at
+/// the given offset, which should point to the prefix of the first bytes literal. The escape
+/// sequences are decoded the way the Python interpreter would.
+fn parse_bytes(source: &[u8], mut offset: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+
+    while matches!(source.get(offset), Some(b'b') | Some(b'B')) {
+        // Skip the prefix.
+        offset += 1;
+
+        let quote = match source.get(offset)? {
+            quote @ (b'"' | b'\'') => *quote,
+            _ => return None,
+        };
+
+        // Skip the opening quote.
+        offset += 1;
+
+        loop {
+            let c = *source.get(offset)?;
+            offset += 1;
+
+            if c == quote {
+                break;
+            }
+
+            if c != b'\\' {
+                bytes.push(c);
+                continue;
+            }
+
+            offset = decode_escape(source, offset, Dialect::Python, &mut bytes)?;
+        }
+
+        offset = skip_whitespace(source, offset);
+    }
+
+    Some(bytes)
+}
+
+/// Looks up the assignment to the variable `name` at the start of a line in the given source, and
+/// parses the bytes literals assigned to it.
+fn parse_variable(source: &[u8], name: &str) -> Option<Vec<u8>> {
+    let mut start = 0;
+
+    for line in source.split(|c| *c == b'\n') {
+        let offset = start;
+        start += line.len() + 1;
+
+        // Check that the line assigns to the variable.
+        let rest = match line.strip_prefix(name.as_bytes()) {
+            Some(rest) => rest,
+            _ => continue,
+        };
+
+        let value = match rest.iter().position(|c| !c.is_ascii_whitespace()) {
+            Some(position) if rest[position] == b'=' => position + 1,
+            _ => continue,
+        };
+
+        let offset = skip_whitespace(source, offset + name.len() + value);
+
+        return parse_bytes(source, offset);
+    }
+
+    None
+}
+
+/// Looks up the format version that is passed to `qRegisterResourceData`, e.g.
+/// `qRegisterResourceData(0x03, ...)`.
+fn parse_version(source: &str) -> Option<u32> {
+    let offset = source.find("qRegisterResourceData(")? + "qRegisterResourceData(".len();
+    let end = source[offset..].find(',')? + offset;
+    let version = source[offset..end].trim();
+
+    parse_integer(version)
+}
+
+/// Parses the Python module generated by pyrcc or pyside-rcc (e.g. `*_rc.py`) in the given byte
+/// array. The arrays are assigned to module-level variables as bytes literals, which are decoded
+/// and concatenated in the order of data, names and tree. pyrcc5 emits separate trees for version 1
+/// and 2 of the format, whereas pyside-rcc passes the version to `qRegisterResourceData`. If the
+/// data array is missing, its offset is left unset.
+///
+/// Yields the concatenated arrays and their offsets, or `None` if the tree or the names are
+/// missing.
+pub fn parse_module(bytes: &[u8]) -> Option<(Vec<u8>, ResourceSymbols)> {
+    let source = std::str::from_utf8(bytes).ok()?;
+
+    // pyrcc5 emits a tree for both version 1 and 2 of the format and picks one at runtime
+    // depending on the Qt version, so prefer the newer one.
+    let (tree, version) = if let Some(tree) = parse_variable(bytes, "qt_resource_struct_v2") {
+        (tree, Some(2))
+    } else if let Some(tree) = parse_variable(bytes, "qt_resource_struct_v1") {
+        (tree, Some(1))
+    } else {
+        (parse_variable(bytes, "qt_resource_struct")?, parse_version(source))
+    };
+
+    let names = parse_variable(bytes, "qt_resource_name")?;
+    let data = parse_variable(bytes, "qt_resource_data");
+    let data_len = data.as_ref().map_or(0, |data| data.len());
+
+    let symbols = ResourceSymbols {
+        data: data.as_ref().map(|_| 0),
+        names: Some(data_len),
+        tree: Some(data_len + names.len()),
+        version,
+    };
+
+    Some(([data.unwrap_or_default(), names, tree].concat(), symbols))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name::hash_str;
+    use crate::symbols::extract_symbols;
+
+    #[test]
+    fn parses_bytes_literals() {
+        let source = b"qt_resource_name = b\"\\\n\\x00\\x01a\\\n\\q\" \\\n  B'\\'\\0'\n";
+
+        assert_eq!(parse_variable(source, "qt_resource_name"), Some(vec![0, 1, b'a', b'\\', b'q', b'\'', 0]));
+        assert_eq!(parse_bytes(b"b\"\\x1\"", 0), None);
+        assert_eq!(parse_bytes(b"b\"abc", 0), None);
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("QtCore.qRegisterResourceData(0x03, qt_resource_struct, ...)"), Some(3));
+        assert_eq!(parse_version("QtCore.qRegisterResourceData(2, qt_resource_struct, ...)"), Some(2));
+        assert_eq!(parse_version("QtCore.qRegisterResourceData(rcc_version, ...)"), None);
+    }
+
+    #[test]
+    fn leaves_missing_data_unset() {
+        let source = b"qt_resource_struct_v2 = b\"\\x01\\x02\"\nqt_resource_name = b\"\\x03\"\n";
+
+        let (bytes, symbols) = parse_module(source).unwrap();
+
+        assert_eq!(bytes, vec![3, 1, 2]);
+        assert_eq!((symbols.data, symbols.names, symbols.tree, symbols.version), (None, Some(0), Some(1), Some(2)));
+    }
+
+    #[test]
+    fn skips_trees_without_data() {
+        let mut names = vec![];
+
+        for name in ["a", "b", "c"] {
+            names.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            names.extend_from_slice(&hash_str(name).to_be_bytes());
+            names.extend(name.encode_utf16().flat_map(|c| c.to_be_bytes()));
+        }
+
+        // A root directory with three files, whose data offsets form a chain of blob sizes that
+        // runs past the end of the names and the tree.
+        let mut tree = vec![];
+
+        for (name_offset, flags, a, b) in [(0u32, 2u16, 3u32, 1u32), (0, 0, 0, 0x18), (8, 0, 0, 0x10000), (16, 0, 0, 0x0a)] {
+            tree.extend_from_slice(&name_offset.to_be_bytes());
+            tree.extend_from_slice(&flags.to_be_bytes());
+            tree.extend_from_slice(&a.to_be_bytes());
+            tree.extend_from_slice(&b.to_be_bytes());
+            tree.extend_from_slice(&0u64.to_be_bytes());
+        }
+
+        let literal = |bytes: &[u8]| bytes.iter().map(|byte| format!("\\x{:02x}", byte)).collect::<String>();
+        let source = format!("qt_resource_struct_v2 = b\"{}\"\nqt_resource_name = b\"{}\"\n", literal(&tree), literal(&names));
+
+        let (bytes, symbols) = parse_module(source.as_bytes()).unwrap();
+
+        assert_eq!((symbols.data, symbols.names, symbols.tree), (None, Some(0), Some(names.len())));
+
+        // The blobs can't be located in the names and the tree alone.
+        let root = std::env::temp_dir().join(format!("qtrc-extract-no-data-{}", std::process::id()));
+
+        assert!(!extract_symbols(&root, &bytes, &symbols).unwrap());
+        assert!(!root.exists());
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::Result;
+use binrw::BinRead;
+use binrw::io::Cursor;
+use rangemap::RangeSet;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::name::parse_names;
+use crate::tree::{collect_data_offsets, entry_size, extract_tree, parse_tree};
+
+/// The header of a binary resource file as produced by `rcc -binary`. The offsets are relative to
+/// the start of the header.
+#[derive(BinRead, Debug)]
+#[br(big, magic = b"qres")]
+pub struct Header {
+    pub version: u32,
+    pub tree_offset: u32,
+    pub data_offset: u32,
+    pub names_offset: u32,
+    #[br(if(version >= 3))]
+    _flags: Option<u32>,
+}
+
+/// Attempts to parse the header of a binary resource file at the start of the given byte array
+/// `bytes`. Checks that the format version is supported and that the offsets fall within the byte
+/// array.
+///
+/// Yields the header if all the sanity checks passed.
+pub fn parse_header(bytes: &[u8]) -> Option<Header> {
+    let mut reader = Cursor::new(bytes);
+
+    let header = match Header::read(&mut reader) {
+        Ok(header) => header,
+        _ => return None,
+    };
+
+    // Is this a format version that we know about?
+    if !(1..=3).contains(&header.version) {
+        return None;
+    }
+
+    // Do the offsets fall within the byte array?
+    let offsets = [header.tree_offset, header.data_offset, header.names_offset];
+
+    if offsets.iter().any(|offset| *offset as usize >= bytes.len()) {
+        return None;
+    }
+
+    Some(header)
+}
+
+/// Extracts the tree of the binary resource file in the given byte array `bytes` to the directory
+/// `root`, using the offsets and the format version stored in the header `header`.
+pub fn extract_qres<P: AsRef<Path>>(
+    root: P,
+    bytes: &[u8],
+    header: &Header,
+) -> Result<()> {
+    let (_, names) = parse_names(&bytes[header.names_offset as usize..], 0);
+
+    extract_tree(
+        root,
+        &names,
+        &bytes[header.data_offset as usize..],
+        &bytes[header.tree_offset as usize..],
+        0,
+        1,
+        header.version,
+    )
+}
+
+/// Checks that every data offset referenced by the tree of the binary resource image in the given
+/// byte array `bytes` points to a blob that lies within the byte array.
+///
+/// Yields the end of the last blob relative to the start of the image, or `None` if any of the
+/// blobs is out of bounds.
+fn blobs_end(bytes: &[u8], header: &Header) -> Option<usize> {
+    let data_offset = header.data_offset as usize;
+    let mut end = data_offset;
+
+    for offset in collect_data_offsets(&bytes[header.tree_offset as usize..], 0, 1, header.version) {
+        let start = data_offset.checked_add(offset)?;
+
+        // Decode the 32-bit size field.
+        let mut slice = [0u8; 4];
+        slice.copy_from_slice(bytes.get(start..)?.get(..4)?);
+        let size = u32::from_be_bytes(slice) as usize;
+
+        // Check that the blob fits.
+        let blob_end = start + 4 + size;
+
+        if blob_end > bytes.len() {
+            return None;
+        }
+
+        end = end.max(blob_end);
+    }
+
+    Some(end)
+}
+
+/// Scans the given byte array `bytes` for binary resource images, such as the ones that get
+/// embedded into an executable to be registered through `QResource::registerResource`. For every
+/// `qres` magic that we find, we check that the header is sane, that the names section contains at
+/// least one name, that the tree references all of those names and that all of the data offsets
+/// point to blobs within the byte array.
+///
+/// Yields a map of image offsets to the range covered by the names, the tree and the blobs, as well
+/// as the header.
+pub fn find_images(
+    bytes: &[u8],
+) -> BTreeMap<usize, (Range<usize>, Header)> {
+    let mut images = BTreeMap::new();
+
+    for (offset, window) in bytes.windows(4).enumerate() {
+        // Look for the magic.
+        if window != b"qres" {
+            continue;
+        }
+
+        let image = &bytes[offset..];
+
+        let header = match parse_header(image) {
+            Some(header) => header,
+            _ => continue,
+        };
+
+        // Parse the names.
+        let (name_range, names) = parse_names(&image[header.names_offset as usize..], 0);
+
+        if names.is_empty() {
+            continue;
+        }
+
+        let name_offsets: HashSet<usize> = names
+            .keys()
+            .copied()
+            .collect();
+
+        // Check that the tree uses all of our name offsets.
+        let mut node_ids = RangeSet::new();
+        let tree_offset = header.tree_offset as usize;
+        let count = parse_tree(&name_offsets, &mut node_ids, &image[tree_offset..], 0, 1, header.version);
+
+        if count < name_offsets.len() {
+            continue;
+        }
+
+        // Check that the data offsets make sense.
+        let data_end = match blobs_end(image, &header) {
+            Some(data_end) => data_end,
+            _ => continue,
+        };
+
+        // Calculate the end of the image from the end of the names, the tree and the blobs.
+        let names_end = header.names_offset as usize + name_range.end;
+        let tree_end = match node_ids.last() {
+            Some(range) => tree_offset + range.end * entry_size(header.version),
+            _ => continue,
+        };
+
+        let end = offset + names_end.max(tree_end).max(data_end);
+
+        images.insert(offset, (offset..end, header));
+    }
+
+    images
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a header of the given format version followed by `padding` zero bytes.
+    fn header(version: u32, offsets: [u32; 3], padding: usize) -> Vec<u8> {
+        let mut bytes = b"qres".to_vec();
+        bytes.extend_from_slice(&version.to_be_bytes());
+
+        for offset in offsets {
+            bytes.extend_from_slice(&offset.to_be_bytes());
+        }
+
+        if version >= 3 {
+            bytes.extend_from_slice(&0u32.to_be_bytes());
+        }
+
+        bytes.resize(bytes.len() + padding, 0);
+        bytes
+    }
+
+    #[test]
+    fn parses_headers_of_every_version() {
+        for version in 1..=3 {
+            let header = parse_header(&header(version, [0x20, 0x30, 0x40], 0x40)).unwrap();
+
+            assert_eq!(header.version, version);
+            assert_eq!(header.tree_offset, 0x20);
+            assert_eq!(header.data_offset, 0x30);
+            assert_eq!(header.names_offset, 0x40);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        // Unknown format versions.
+        assert!(parse_header(&header(0, [0x20, 0x30, 0x40], 0x40)).is_none());
+        assert!(parse_header(&header(4, [0x20, 0x30, 0x40], 0x40)).is_none());
+
+        // Offsets past the end.
+        assert!(parse_header(&header(2, [0x20, 0x30, 0x100], 0x40)).is_none());
+
+        // Missing magic.
+        assert!(parse_header(&header(2, [0x20, 0x30, 0x40], 0x40)[4..]).is_none());
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::Result;
+use binrw::BinRead;
+use binrw::io::Cursor;
+use goblin::Object;
+use std::ops::Range;
+
+use crate::executable::ExecutableMapping;
+
+/// The resource type of raw application-defined data.
+const RT_RCDATA: u32 = 10;
+
+/// Directory entries whose name or offset field has this bit set refer to a string or to a
+/// subdirectory respectively.
+const HIGH_BIT: u32 = 0x8000_0000;
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct Directory {
+    _characteristics: u32,
+    _timestamp: u32,
+    _major_version: u16,
+    _minor_version: u16,
+    named_count: u16,
+    id_count: u16,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct DirectoryEntry {
+    name: u32,
+    offset: u32,
+}
+
+#[derive(BinRead, Debug)]
+#[br(little)]
+pub struct DataEntry {
+    rva: u32,
+    size: u32,
+    _code_page: u32,
+    _reserved: u32,
+}
+
+/// A leaf of the resource directory.
+#[derive(Debug)]
+pub struct Resource {
+    /// The resource type, e.g. `RCDATA`.
+    pub kind: String,
+    /// The resource name.
+    pub name: String,
+    /// The resource language.
+    pub language: String,
+    /// The file range of the resource data.
+    pub file_range: Range<usize>,
+}
+
+/// Decodes the name or ID of a directory entry. Names are stored as UTF-16 LE strings, prefixed
+/// with their 16-bit length in characters, relative to the start of the resource directory.
+fn entry_name(bytes: &[u8], name: u32) -> Option<String> {
+    if name & HIGH_BIT == 0 {
+        return Some(name.to_string());
+    }
+
+    let offset = (name & !HIGH_BIT) as usize;
+    let length = u16::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?]) as usize;
+
+    let name: Vec<u16> = bytes.get(offset + 2..)?.get(..length * 2)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    // Make sure the name can be used as a path component, which also rules out names that refer
+    // to the directory itself or its parent.
+    let name = String::from_utf16_lossy(&name).replace(['/', '\\'], "_");
+
+    match name.as_str() {
+        "" | "." | ".." => Some("_".repeat(name.len().max(1))),
+        _ => Some(name),
+    }
+}
+
+/// Walks the directory at the offset `offset` within the resource directory `bytes`. The path
+/// `path` holds the names of the directory entries that lead to this directory, i.e. the type and
+/// the name. The data entries of the language directories are collected in `resources`, where the
+/// relative virtual addresses are resolved to file offsets through the mapping `mapping` of the
+/// image loaded at `image_base`.
+fn walk_directory(
+    bytes: &[u8],
+    mapping: &ExecutableMapping,
+    image_base: usize,
+    offset: usize,
+    path: &mut Vec<String>,
+    resources: &mut Vec<Resource>,
+) {
+    let mut reader = Cursor::new(bytes);
+    reader.set_position(offset as u64);
+
+    let directory = match Directory::read(&mut reader) {
+        Ok(directory) => directory,
+        _ => return,
+    };
+
+    let count = directory.named_count as usize + directory.id_count as usize;
+
+    for _ in 0..count {
+        let entry = match DirectoryEntry::read(&mut reader) {
+            Ok(entry) => entry,
+            _ => break,
+        };
+
+        let name = match entry_name(bytes, entry.name) {
+            Some(name) => name,
+            _ => continue,
+        };
+
+        // The directory consists of three levels: the type, the name and the language. Anything
+        // deeper than that is malformed, and might even be a loop.
+        if entry.offset & HIGH_BIT != 0 {
+            if path.len() < 2 {
+                let position = reader.position();
+
+                path.push(name);
+                walk_directory(bytes, mapping, image_base, (entry.offset & !HIGH_BIT) as usize, path, resources);
+                path.pop();
+
+                reader.set_position(position);
+            }
+
+            continue;
+        }
+
+        if path.len() != 2 {
+            continue;
+        }
+
+        // Parse the data entry, which points to the resource data.
+        let position = reader.position();
+        reader.set_position(entry.offset as u64);
+
+        let data = DataEntry::read(&mut reader);
+        reader.set_position(position);
+
+        let data = match data {
+            Ok(data) => data,
+            _ => continue,
+        };
+
+        let start = match mapping.rva_to_file_offset(image_base + data.rva as usize) {
+            Some(start) => start,
+            _ => continue,
+        };
+
+        // Use the symbolic name for the resource type we are interested in.
+        let kind = match path[0].parse::<u32>() {
+            Ok(RT_RCDATA) => "RCDATA".to_string(),
+            _ => path[0].clone(),
+        };
+
+        resources.push(Resource {
+            kind,
+            name: path[1].clone(),
+            language: name,
+            file_range: start..start + data.size as usize,
+        });
+    }
+}
+
+/// Walks the resource directory of the PE image in the given byte array `bytes`, whose sections
+/// are mapped by `mapping`.
+///
+/// Yields the resources that may contain rcc data, i.e. those of type `RCDATA` and those with an
+/// application-defined type name, or an empty list if the given byte array is not a PE image.
+pub fn rcdata_resources(bytes: &[u8], mapping: &ExecutableMapping) -> Result<Vec<Resource>> {
+    let mut resources = vec![];
+
+    let pe = match Object::parse(bytes)? {
+        Object::PE(pe) => pe,
+        _ => return Ok(resources),
+    };
+
+    let table = match pe.header.optional_header.and_then(|header| *header.data_directories.get_resource_table()) {
+        Some(table) => table,
+        _ => return Ok(resources),
+    };
+
+    let image_base = pe.image_base;
+
+    let start = match mapping.rva_to_file_offset(image_base + table.virtual_address as usize) {
+        Some(start) => start,
+        _ => return Ok(resources),
+    };
+
+    let directory = match bytes.get(start..).and_then(|bytes| bytes.get(..table.size as usize)) {
+        Some(directory) => directory,
+        _ => return Ok(resources),
+    };
+
+    walk_directory(directory, mapping, image_base, 0, &mut vec![], &mut resources);
+
+    // Only keep the resources that may contain rcc data and that are within the file.
+    resources.retain(|resource| {
+        let kind = resource.kind.as_str();
+
+        resource.file_range.end <= bytes.len() &&
+            (kind == "RCDATA" || kind.parse::<u32>().is_err())
+    });
+
+    Ok(resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes the given name as a UTF-16 LE string, prefixed with its length in characters.
+    fn name(name: &str) -> Vec<u8> {
+        let name: Vec<u16> = name.encode_utf16().collect();
+        let mut bytes = (name.len() as u16).to_le_bytes().to_vec();
+
+        for c in name {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn decodes_entry_names() {
+        assert_eq!(entry_name(&[], 10).as_deref(), Some("10"));
+        assert_eq!(entry_name(&name("ICONS"), HIGH_BIT).as_deref(), Some("ICONS"));
+        assert_eq!(entry_name(&name("ICONS")[..4], HIGH_BIT), None);
+    }
+
+    #[test]
+    fn sanitizes_entry_names() {
+        assert_eq!(entry_name(&name("../icons\\app"), HIGH_BIT).as_deref(), Some(".._icons_app"));
+        assert_eq!(entry_name(&name(".."), HIGH_BIT).as_deref(), Some("__"));
+        assert_eq!(entry_name(&name("."), HIGH_BIT).as_deref(), Some("_"));
+        assert_eq!(entry_name(&name(""), HIGH_BIT).as_deref(), Some("_"));
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::name::parse_names;
+use crate::tree;
+
+/// The arrays that rcc emits for every qrc file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Array {
+    /// `qt_resource_struct`, which holds the tree.
+    Tree,
+    /// `qt_resource_name`, which holds the names.
+    Names,
+    /// `qt_resource_data`, which holds the blobs.
+    Data,
+}
+
+/// The file offsets of the arrays that rcc emitted for a single qrc file.
+#[derive(Debug, Default)]
+pub struct ResourceSymbols {
+    pub tree: Option<usize>,
+    pub names: Option<usize>,
+    pub data: Option<usize>,
+    /// The format version, if known.
+    pub version: Option<u32>,
+}
+
+impl ResourceSymbols {
+    fn get_mut(&mut self, array: Array) -> &mut Option<usize> {
+        match array {
+            Array::Tree => &mut self.tree,
+            Array::Names => &mut self.names,
+            Array::Data => &mut self.data,
+        }
+    }
+}
+
+/// Decodes the Itanium mangled name of a variable or function, and yields its unqualified name,
+/// e.g. `qt_resource_data` for `_ZL16qt_resource_data` or `qInitResources_app` for
+/// `_ZN2ns18qInitResources_appEv`.
+fn demangle_itanium(name: &str) -> Option<&str> {
+    let mut rest = name.strip_prefix("_Z")?;
+
+    // Skip the marker for internal linkage.
+    rest = rest.strip_prefix('L').unwrap_or(rest);
+
+    // Check if this is a nested name.
+    let nested = rest.starts_with('N');
+
+    if nested {
+        rest = &rest[1..];
+    }
+
+    // Decode the length-prefixed components and keep the last one.
+    let mut last = None;
+
+    loop {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+
+        if digits == 0 {
+            break;
+        }
+
+        let size: usize = rest[..digits].parse().ok()?;
+        last = Some(rest.get(digits..digits + size)?);
+        rest = &rest[digits + size..];
+
+        if !nested {
+            break;
+        }
+    }
+
+    last
+}
+
+/// Yields the unqualified name of the given symbol name. Apart from plain names, this understands
+/// names as mangled by MSVC (e.g. `?qt_resource_data@@3QBEB`) and Itanium C++ compilers (e.g.
+/// `_ZL16qt_resource_data`), the leading underscore used by Mach-O and suffixes added by the
+/// compiler to disambiguate local symbols (e.g. `qt_resource_data.lto_priv.0`).
+pub fn unqualified_name(name: &str) -> &str {
+    // Strip the MSVC decoration.
+    if let Some(name) = name.strip_prefix('?') {
+        return name.split('@').next().unwrap_or(name);
+    }
+
+    // Strip the leading underscore used by Mach-O.
+    let name = match name.strip_prefix('_') {
+        Some(stripped) if stripped.starts_with("_Z") || stripped.starts_with("qt_") => stripped,
+        _ => name,
+    };
+
+    // Decode the Itanium mangled name.
+    let name = demangle_itanium(name).unwrap_or(name);
+
+    // Strip any compiler suffixes.
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Checks whether the symbol name refers to one of the arrays emitted by rcc.
+pub fn resource_array(name: &str) -> Option<Array> {
+    match unqualified_name(name) {
+        "qt_resource_struct" => Some(Array::Tree),
+        "qt_resource_name" => Some(Array::Names),
+        "qt_resource_data" => Some(Array::Data),
+        _ => None,
+    }
+}
+
+/// Groups the given symbols, which consist of a symbol name and a file offset, into sets of arrays
+/// that belong to the same qrc file. As the arrays of a qrc file end up next to each other in the
+/// symbol table, we simply start a new set whenever we see an array that is already part of the
+/// current set.
+pub fn group_symbols<'a, I: IntoIterator<Item = (&'a str, usize)>>(
+    symbols: I,
+) -> Vec<ResourceSymbols> {
+    let mut groups = vec![];
+    let mut group = ResourceSymbols::default();
+
+    for (name, offset) in symbols {
+        let array = match resource_array(name) {
+            Some(array) => array,
+            _ => continue,
+        };
+
+        // Start a new set if we already saw this array.
+        if group.get_mut(array).is_some() {
+            groups.push(std::mem::take(&mut group));
+        }
+
+        *group.get_mut(array) = Some(offset);
+    }
+
+    groups.push(group);
+
+    // We need at least the tree and the names.
+    groups
+        .into_iter()
+        .filter(|group| group.tree.is_some() && group.names.is_some())
+        .collect()
+}
+
+/// Extracts the tree described by the set of arrays `symbols` from the given byte array `bytes` to
+/// the directory `root`. Unless the format version is known, it is detected from the tree itself.
+/// If the set lacks the data array, we try to find the blobs using the data offsets in the tree
+/// instead.
+///
+/// Yields whether the tree could be extracted.
+pub fn extract_symbols<P: AsRef<Path>>(
+    root: P,
+    bytes: &[u8],
+    symbols: &ResourceSymbols,
+) -> Result<bool> {
+    let (tree_offset, names_offset) = match (symbols.tree, symbols.names) {
+        (Some(tree_offset), Some(names_offset)) if tree_offset < bytes.len() && names_offset < bytes.len() => {
+            (tree_offset, names_offset)
+        }
+        _ => return Ok(false),
+    };
+
+    let (_, names) = parse_names(bytes, names_offset);
+
+    let name_offsets: HashSet<usize> = names
+        .keys()
+        .copied()
+        .collect();
+
+    // Detect the format version.
+    let version = match symbols.version {
+        Some(version) => version,
+        _ => match tree::detect_tree(&name_offsets, &bytes[tree_offset..]) {
+            Some((version, _)) => version,
+            _ => return Ok(false),
+        },
+    };
+
+    let data_offset = match symbols.data {
+        Some(data_offset) if data_offset < bytes.len() => data_offset,
+        _ => match tree::find_blobs(tree_offset, bytes, version).into_keys().next() {
+            Some(data_offset) => data_offset,
+            _ => return Ok(false),
+        },
+    };
+
+    println!("Found tree at 0x{:x}, names at 0x{:x} and data at 0x{:x} (version {})...", tree_offset, names_offset, data_offset, version);
+    println!("Extracting file tree...");
+
+    tree::extract_tree(root, &names, &bytes[data_offset..], &bytes[tree_offset..], 0, 1, version)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_itanium_names() {
+        assert_eq!(demangle_itanium("_ZL16qt_resource_data"), Some("qt_resource_data"));
+        assert_eq!(demangle_itanium("_ZN2ns18qInitResources_appEv"), Some("qInitResources_app"));
+        assert_eq!(demangle_itanium("_ZN12_GLOBAL__N_116qt_resource_nameE"), Some("qt_resource_name"));
+
+        // Not mangled, or truncated.
+        assert_eq!(demangle_itanium("qt_resource_data"), None);
+        assert_eq!(demangle_itanium("_ZL16qt_resource"), None);
+        assert_eq!(demangle_itanium("_ZNK"), None);
+    }
+
+    #[test]
+    fn resolves_unqualified_names() {
+        assert_eq!(unqualified_name("?qt_resource_data@@3QBEB"), "qt_resource_data");
+        assert_eq!(unqualified_name("__ZL16qt_resource_data"), "qt_resource_data");
+        assert_eq!(unqualified_name("_qt_resource_struct"), "qt_resource_struct");
+        assert_eq!(unqualified_name("qt_resource_name.lto_priv.0"), "qt_resource_name");
+        assert_eq!(resource_array("_ZL18qt_resource_struct"), Some(Array::Tree));
+    }
+}
@@ -32,13 +32,28 @@ pub enum EntryData {
 }
 
 #[derive(BinRead, Debug)]
-#[br(big)]
+#[br(big, import(version: u32))]
 pub struct Entry {
     name_offset: u32,
     flags: u16,
     #[br(args { flags })]
     data: EntryData,
-    _last_modified: u64,
+    #[br(if(version >= 2))]
+    _last_modified: Option<u64>,
+}
+
+/// The format versions to try when the format version is not known, in order of preference.
+/// Versions 2 and 3 share the same layout, but only version 3 allows zstd compressed files.
+pub const VERSIONS: [u32; 3] = [2, 3, 1];
+
+/// Yields the size of a tree entry in bytes for the given format version. Version 1 of the format
+/// lacks the 64-bit last modified timestamp.
+pub fn entry_size(version: u32) -> usize {
+    if version >= 2 {
+        22
+    } else {
+        14
+    }
 }
 
 /// Attempts to parse a tree from the given byte array `bytes`. The node ID `node_id` and node
@@ -48,6 +63,8 @@ pub struct Entry {
 /// While parsing each tree entry, the name offset is checked against the `name_offsets` HashSet to
 /// ensure that the name offset is valid.
 ///
+/// The format version `version` determines the layout of the tree entries.
+///
 /// Yields 0 if any of the sanity checks failed. Otherwise returns the number of valid name offsets
 /// that we have seen.
 pub fn parse_tree(
@@ -56,14 +73,17 @@ pub fn parse_tree(
     bytes: &[u8],
     node_id: usize,
     count: usize,
+    version: u32,
 ) -> usize {
+    let size = entry_size(version);
+
     // Check that we have enough bytes for the node ID to make sense.
-    if bytes.len() / 22 <= node_id {
+    if bytes.len() / size <= node_id {
         return 0;
     }
 
     // Check that we have enough bytes for the node count to make sense.
-    if bytes.len() / 22 - node_id <= count {
+    if bytes.len() / size - node_id < count {
         return 0;
     }
 
@@ -78,12 +98,12 @@ pub fn parse_tree(
     node_ids.insert(node_id..node_id + count);
 
     // Parse the entries.
-    let mut reader = Cursor::new(&bytes[node_id * 22..][..count * 22]);
+    let mut reader = Cursor::new(&bytes[node_id * size..][..count * size]);
     let mut result = 0;
 
     for _ in 0..count {
         // Read the current entry.
-        let entry = match Entry::read(&mut reader) {
+        let entry = match Entry::read_args(&mut reader, (version,)) {
             Ok(entry) => entry,
             _ => return 0,
         };
@@ -93,14 +113,21 @@ pub fn parse_tree(
             return 0;
         }
 
-        // Do the flags make sense?
-        if entry.flags > 2 {
+        // Do the flags make sense? Directories are never compressed, and files are either
+        // uncompressed or zlib compressed. Version 3 of the format adds zstd compression.
+        let valid = match entry.flags {
+            0..=2 => true,
+            4 => version >= 3,
+            _ => false,
+        };
+
+        if !valid {
             return 0;
         }
 
         // Parse the directory.
         if let EntryData::Directory { node_id, count, .. } = entry.data {
-            let count = parse_tree(name_offsets, node_ids, bytes, node_id as usize, count as usize);
+            let count = parse_tree(name_offsets, node_ids, bytes, node_id as usize, count as usize, version);
 
             // OK, something failed while parsing the directory.
             if count == 0 {
@@ -124,32 +151,34 @@ pub fn collect_data_offsets(
     bytes: &[u8],
     node_id: usize,
     count: usize,
+    version: u32,
 ) -> BTreeSet<usize> {
+    let size = entry_size(version);
     let mut offsets = BTreeSet::new();
 
     // Check that we have enough bytes for the node ID to make sense.
-    if bytes.len() / 22 <= node_id {
+    if bytes.len() / size <= node_id {
         return offsets;
     }
 
     // Check that we have enough bytes for the node count to make sense.
-    if bytes.len() / 22 - node_id <= count {
+    if bytes.len() / size - node_id < count {
         return offsets;
     }
 
     // Parse the entries.
-    let mut reader = Cursor::new(&bytes[node_id * 22..][..count * 22]);
+    let mut reader = Cursor::new(&bytes[node_id * size..][..count * size]);
 
     for _ in 0..count {
         // Read the current entry.
-        let entry = match Entry::read(&mut reader) {
+        let entry = match Entry::read_args(&mut reader, (version,)) {
             Ok(entry) => entry,
             _ => continue,
         };
 
         match entry.data {
             EntryData::Directory { node_id, count, .. } => {
-                for offset in collect_data_offsets(bytes, node_id as usize, count as usize) {
+                for offset in collect_data_offsets(bytes, node_id as usize, count as usize, version) {
                     offsets.insert(offset);
                 }
             }
@@ -162,10 +191,43 @@ pub fn collect_data_offsets(
     offsets
 }
 
+/// Attempts to parse a tree at the start of the given byte array `bytes` using the layout of every
+/// format version in `VERSIONS`. A layout is only accepted if the tree references all the name
+/// offsets in `name_offsets`.
+///
+/// Yields the format version of the first layout that was accepted together with the size of the
+/// tree in bytes.
+pub fn detect_tree(
+    name_offsets: &HashSet<usize>,
+    bytes: &[u8],
+) -> Option<(u32, usize)> {
+    for version in VERSIONS {
+        let mut node_ids = RangeSet::new();
+
+        // Try parsing the tree using the layout of this version.
+        let count = parse_tree(name_offsets, &mut node_ids, bytes, 0, 1, version);
+
+        // Did this tree use all of our name offsets?
+        if count < name_offsets.len() {
+            continue;
+        }
+
+        if let Some(range) = node_ids.last() {
+            return Some((version, range.end * entry_size(version)));
+        }
+    }
+
+    None
+}
+
+/// Scans the given byte array `bytes` for trees that reference all of the names in `names`. Every
+/// offset is tried with every layout, see `detect_tree`.
+///
+/// Yields a map of the tree offsets to the tree range and the format version that was detected.
 pub fn find_trees(
     names: &BTreeMap<usize, String>,
     bytes: &[u8],
-) -> BTreeMap<usize, Range<usize>> {
+) -> BTreeMap<usize, (Range<usize>, u32)> {
     let mut sections = BTreeMap::new();
 
     // Collect the name offsets.
@@ -175,19 +237,9 @@ pub fn find_trees(
         .collect();
 
     for offset in (0..bytes.len()).step_by(8).rev() {
-        let mut node_ids = RangeSet::new();
-
         // Try parsing the current offset as a tree.
-        let count = parse_tree(&name_offsets, &mut node_ids, &bytes[offset..], 0, 1);
-
-        // Did this tree use all of our name offsets?
-        if count >= name_offsets.len() {
-            let end = match node_ids.iter().last() {
-                Some(range) => range.end,
-                _ => continue,
-            };
-
-            sections.insert(offset, offset..offset + end * 22);
+        if let Some((version, size)) = detect_tree(&name_offsets, &bytes[offset..]) {
+            sections.insert(offset, (offset..offset + size, version));
         }
     }
 
@@ -197,18 +249,25 @@ pub fn find_trees(
 pub fn find_blobs(
     tree_offset: usize,
     bytes: &[u8],
+    version: u32,
 ) -> BTreeMap<usize, Range<usize>> {
     let mut sections = BTreeMap::new();
 
-    let offsets = collect_data_offsets(&bytes[tree_offset..], 0, 1);
+    let offsets = collect_data_offsets(&bytes[tree_offset..], 0, 1, version);
     let offsets: Vec<usize> = offsets.into_iter().collect();
 
-    // Calculate the deltas between the ordered data offsets.
-    let deltas: Vec<usize> = offsets
+    // Calculate the deltas between the ordered data offsets. Every blob is preceded by its 32-bit
+    // size field, so offsets that are closer together can't be right.
+    let deltas: Option<Vec<usize>> = offsets
         .windows(2)
-        .map(|pair| pair[1] - pair[0] - 4)
+        .map(|pair| (pair[1] - pair[0]).checked_sub(4))
         .collect();
 
+    let deltas = match deltas {
+        Some(deltas) => deltas,
+        _ => return sections,
+    };
+
     if let Some(first) = deltas.first() {
         let first = *first;
 
@@ -233,9 +292,17 @@ pub fn find_blobs(
                 // Point to the next blob.
                 offset = offset + size + 4;
 
-                // Decode the 32-bit size field.
+                // Decode the 32-bit size field, which must lie within the byte array.
                 let mut slice = [0u8; 4];
-                slice.copy_from_slice(&bytes[offset..][..4]);
+
+                match bytes.get(offset..).and_then(|bytes| bytes.get(..4)) {
+                    Some(field) => slice.copy_from_slice(field),
+                    _ => {
+                        found = false;
+                        break;
+                    }
+                }
+
                 size = u32::from_be_bytes(slice) as usize;
 
                 // Check if it matches with the next delta in the chain.
@@ -247,10 +314,12 @@ pub fn find_blobs(
 
             // Did we find a complete chain?
             if found {
-                // Point to the end of the last blob.
+                // Point to the end of the last blob, which must lie within the byte array as well.
                 offset = offset + size + 4;
 
-                sections.insert(start, start..offset);
+                if offset <= bytes.len() {
+                    sections.insert(start, start..offset);
+                }
             }
         }
     }
@@ -265,23 +334,25 @@ pub fn extract_tree<P: AsRef<Path>>(
     bytes: &[u8],
     node_id: usize,
     count: usize,
+    version: u32,
 ) -> Result<()> {
+    let size = entry_size(version);
     // Check that we have enough bytes for the node ID to make sense.
-    if bytes.len() / 22 <= node_id {
+    if bytes.len() / size <= node_id {
         return Ok(());
     }
 
     // Check that we have enough bytes for the node count to make sense.
-    if bytes.len() / 22 - node_id <= count {
+    if bytes.len() / size - node_id < count {
         return Ok(());
     }
 
     // Parse the entries.
-    let mut reader = Cursor::new(&bytes[node_id * 22..][..count * 22]);
+    let mut reader = Cursor::new(&bytes[node_id * size..][..count * size]);
 
     for _ in 0..count {
         // Read the current entry.
-        let entry = match Entry::read(&mut reader) {
+        let entry = match Entry::read_args(&mut reader, (version,)) {
             Ok(entry) => entry,
             _ => continue,
         };
@@ -298,10 +369,17 @@ pub fn extract_tree<P: AsRef<Path>>(
         match entry.data {
             EntryData::Directory { node_id, count, .. } => {
                 std::fs::create_dir_all(&path)?;
-                extract_tree(&path, names, blobs, bytes, node_id as usize, count as usize)?;
+                extract_tree(&path, names, blobs, bytes, node_id as usize, count as usize, version)?;
             }
             EntryData::File { data_offset, .. } => {
-                let mut reader = Cursor::new(&blobs[data_offset as usize..]);
+                // The data offset may point past the end of the blobs, e.g. when dealing with
+                // incomplete memory dumps.
+                let blob = match blobs.get(data_offset as usize..) {
+                    Some(blob) => blob,
+                    _ => continue,
+                };
+
+                let mut reader = Cursor::new(blob);
 
                 // Parse the blob.
                 let blob = match Blob::read(&mut reader) {
@@ -314,6 +392,12 @@ pub fn extract_tree<P: AsRef<Path>>(
                     let mut z = ZlibDecoder::new(&blob.bytes[4..]);
                     z.read_to_end(&mut bytes)?;
 
+                    bytes
+                } else if entry.flags & 4 == 4 {
+                    let mut bytes = vec![];
+                    let mut z = zstd::stream::read::Decoder::new(&blob.bytes[..])?;
+                    z.read_to_end(&mut bytes)?;
+
                     bytes
                 } else {
                     blob.bytes
@@ -327,3 +411,102 @@ pub fn extract_tree<P: AsRef<Path>>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Encodes a tree entry using the layout of the given format version, where `a` and `b` are
+    /// either the count and node ID of a directory, or the locale and data offset of a file.
+    fn entry(name_offset: u32, flags: u16, a: u32, b: u32, version: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.extend_from_slice(&name_offset.to_be_bytes());
+        bytes.extend_from_slice(&flags.to_be_bytes());
+        bytes.extend_from_slice(&a.to_be_bytes());
+        bytes.extend_from_slice(&b.to_be_bytes());
+
+        if version >= 2 {
+            bytes.extend_from_slice(&0u64.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    /// Creates an empty temporary directory for the given test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("qtrc-extract-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn extracts_zstd_compressed_blobs() {
+        let contents = b"hello hello hello hello hello".to_vec();
+        let compressed = zstd::encode_all(&contents[..], 0).unwrap();
+
+        // A root directory with a single zstd compressed file.
+        let mut tree = entry(0, 2, 1, 1, 3);
+        tree.extend(entry(10, 4, 0, 0, 3));
+
+        let mut blobs = (compressed.len() as u32).to_be_bytes().to_vec();
+        blobs.extend(compressed);
+
+        let names = BTreeMap::from([(0, "root".to_string()), (10, "file.txt".to_string())]);
+        let root = temp_dir("zstd");
+
+        extract_tree(&root, &names, &blobs, &tree, 0, 1, 3).unwrap();
+
+        assert_eq!(std::fs::read(root.join("root/file.txt")).unwrap(), contents);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn entry_sizes() {
+        assert_eq!(entry_size(1), 14);
+        assert_eq!(entry_size(2), 22);
+        assert_eq!(entry_size(3), 22);
+
+        for version in VERSIONS {
+            assert_eq!(entry(0, 0, 0, 0, version).len(), entry_size(version));
+        }
+    }
+
+    #[test]
+    fn stops_blob_chains_at_the_end() {
+        // A root directory with three files, where the chain of blob sizes that starts at the data
+        // offset of the last file runs past the end of the byte array.
+        let mut tree = entry(0, 2, 3, 1, 2);
+        tree.extend(entry(10, 0, 0, 0x18, 2));
+        tree.extend(entry(20, 0, 0, 0x10000, 2));
+        tree.extend(entry(30, 0, 0, 0x0a, 2));
+
+        assert!(find_blobs(0, &tree, 2).is_empty());
+
+        // Data offsets that are too close together to hold a size field.
+        let mut tree = entry(0, 2, 2, 1, 2);
+        tree.extend(entry(10, 0, 0, 0, 2));
+        tree.extend(entry(20, 0, 0, 2, 2));
+
+        assert!(find_blobs(0, &tree, 2).is_empty());
+    }
+
+    #[test]
+    fn parses_version_1_trees() {
+        let mut tree = entry(0, 2, 2, 1, 1);
+        tree.extend(entry(10, 0, 0, 0, 1));
+        tree.extend(entry(20, 1, 0, 8, 1));
+
+        let name_offsets = HashSet::from([0, 10, 20]);
+        let mut node_ids = RangeSet::new();
+
+        assert_eq!(parse_tree(&name_offsets, &mut node_ids, &tree, 0, 1, 1), 3);
+        assert_eq!(collect_data_offsets(&tree, 0, 1, 1), BTreeSet::from([0, 8]));
+
+        // The tree is too short for the layout of version 2.
+        assert_eq!(detect_tree(&name_offsets, &tree), Some((1, tree.len())));
+    }
+}
@@ -0,0 +1,250 @@
+use std::ops::Range;
+
+/// The magic that every WebAssembly module starts with.
+const MAGIC: &[u8] = b"\0asm";
+
+/// The section ID of the code section.
+const SECTION_CODE: u8 = 10;
+
+/// The section ID of the data section.
+const SECTION_DATA: u8 = 11;
+
+/// Decodes an unsigned LEB128 integer at the given offset and advances the offset past it.
+fn read_uleb128(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+
+        if shift >= 64 {
+            return None;
+        }
+
+        value |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+}
+
+/// Decodes a signed LEB128 integer at the given offset and advances the offset past it.
+pub fn read_sleb128(bytes: &[u8], offset: &mut usize) -> Option<i64> {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+
+        if shift >= 64 {
+            return None;
+        }
+
+        value |= ((byte & 0x7f) as i64) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            // Sign extend the value.
+            if shift < 64 && byte & 0x40 != 0 {
+                value |= -1 << shift;
+            }
+
+            return Some(value);
+        }
+    }
+}
+
+/// Checks whether the given byte array is a WebAssembly module.
+pub fn is_wasm(bytes: &[u8]) -> bool {
+    bytes.len() >= 8 && &bytes[..4] == MAGIC
+}
+
+/// Walks the sections of the WebAssembly module in the given byte array. Yields the section ID and
+/// the file range of the contents of every section.
+pub fn parse_sections(bytes: &[u8]) -> Vec<(u8, Range<usize>)> {
+    let mut sections = vec![];
+
+    if !is_wasm(bytes) {
+        return sections;
+    }
+
+    // Skip the magic and the version.
+    let mut offset = 8;
+
+    while offset < bytes.len() {
+        let id = bytes[offset];
+        offset += 1;
+
+        // Decode the size of the section.
+        let size = match read_uleb128(bytes, &mut offset) {
+            Some(size) => size as usize,
+            _ => break,
+        };
+
+        // Check that the section fits within the byte array.
+        if bytes.len() - offset < size {
+            break;
+        }
+
+        sections.push((id, offset..offset + size));
+        offset += size;
+    }
+
+    sections
+}
+
+/// Looks up the file range of the code section of the WebAssembly module in the given byte array.
+pub fn code_section(bytes: &[u8]) -> Option<Range<usize>> {
+    parse_sections(bytes)
+        .into_iter()
+        .find(|(id, _)| *id == SECTION_CODE)
+        .map(|(_, range)| range)
+}
+
+/// Parses the active data segments of the WebAssembly module in the given byte array. Only the
+/// segments that get placed at a constant address in linear memory are considered, since we cannot
+/// tell where the other segments end up.
+///
+/// Yields the linear memory address and the file range of every data segment.
+pub fn data_segments(bytes: &[u8]) -> Vec<(usize, Range<usize>)> {
+    let mut segments = vec![];
+
+    let range = match parse_sections(bytes).into_iter().find(|(id, _)| *id == SECTION_DATA) {
+        Some((_, range)) => range,
+        _ => return segments,
+    };
+
+    let bytes = &bytes[..range.end];
+    let mut offset = range.start;
+
+    let count = match read_uleb128(bytes, &mut offset) {
+        Some(count) => count,
+        _ => return segments,
+    };
+
+    for _ in 0..count {
+        // Decode the segment flags.
+        let flags = match read_uleb128(bytes, &mut offset) {
+            Some(flags) => flags,
+            _ => break,
+        };
+
+        // Skip the memory index if it is present.
+        if flags == 2 && read_uleb128(bytes, &mut offset).is_none() {
+            break;
+        }
+
+        // Decode the offset expression of active segments. We only understand `i32.const`.
+        let address = if flags == 0 || flags == 2 {
+            let opcode = match bytes.get(offset) {
+                Some(opcode) => *opcode,
+                _ => break,
+            };
+
+            offset += 1;
+
+            let value = match read_sleb128(bytes, &mut offset) {
+                Some(value) => value,
+                _ => break,
+            };
+
+            // Check for the end opcode.
+            if bytes.get(offset) != Some(&0x0b) {
+                break;
+            }
+
+            offset += 1;
+
+            if opcode == 0x41 {
+                Some(value as u32 as usize)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        // Decode the size of the segment.
+        let size = match read_uleb128(bytes, &mut offset) {
+            Some(size) => size as usize,
+            _ => break,
+        };
+
+        // Check that the segment fits within the data section.
+        if bytes.len() - offset < size {
+            break;
+        }
+
+        if let Some(address) = address {
+            segments.push((address, offset..offset + size));
+        }
+
+        offset += size;
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_unsigned_leb128() {
+        let bytes = [0x00, 0x7f, 0xe5, 0x8e, 0x26, 0x80];
+        let mut offset = 0;
+
+        assert_eq!(read_uleb128(&bytes, &mut offset), Some(0));
+        assert_eq!(read_uleb128(&bytes, &mut offset), Some(127));
+        assert_eq!(read_uleb128(&bytes, &mut offset), Some(624485));
+        assert_eq!(offset, 5);
+
+        // The last integer is truncated.
+        assert_eq!(read_uleb128(&bytes, &mut offset), None);
+    }
+
+    #[test]
+    fn decodes_signed_leb128() {
+        let bytes = [0x02, 0x7e, 0xc0, 0xbb, 0x78, 0x80, 0x80, 0x04];
+        let mut offset = 0;
+
+        assert_eq!(read_sleb128(&bytes, &mut offset), Some(2));
+        assert_eq!(read_sleb128(&bytes, &mut offset), Some(-2));
+        assert_eq!(read_sleb128(&bytes, &mut offset), Some(-123456));
+        assert_eq!(read_sleb128(&bytes, &mut offset), Some(0x10000));
+        assert_eq!(offset, bytes.len());
+    }
+
+    #[test]
+    fn parses_active_data_segments() {
+        // A module with a data section holding an active segment at 0x400, a passive segment and
+        // an empty active segment.
+        let mut module = MAGIC.to_vec();
+        module.extend_from_slice(&[1, 0, 0, 0]);
+
+        let section = [
+            3,
+            0, 0x41, 0x80, 0x08, 0x0b, 3, b'a', b'b', b'c',
+            1, 2, b'd', b'e',
+            0, 0x41, 0x00, 0x0b, 0,
+        ];
+
+        module.push(SECTION_DATA);
+        module.push(section.len() as u8);
+        module.extend_from_slice(&section);
+
+        let start = module.len() - section.len();
+
+        assert_eq!(data_segments(&module), vec![(0x400, start + 7..start + 10), (0, start + 19..start + 19)]);
+
+        // The empty segment must not end up in the mapping.
+        let mapping = crate::executable::ExecutableMapping::parse(&module).unwrap();
+
+        assert_eq!(mapping.rva_to_file_offset(0x401), Some(start + 8));
+        assert_eq!(mapping.rva_to_file_offset(0), None);
+    }
+}